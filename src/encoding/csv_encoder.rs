@@ -0,0 +1,90 @@
+use crate::encoding::response_encoder::{get_type_name, ResponseEncoder};
+use crate::query::query_resolver::QueryResponse;
+use qdb_ast::ast::types::DataType;
+
+// CSV (RFC 4180) of response, one line per value of row:
+//     from,to,name,type,value
+//     0,1,c,int,2
+//     0,1,d,null,
+// response without result has only header
+pub struct CsvEncoder;
+
+impl CsvEncoder {
+    // hidden function
+    fn write_field(csv: &mut String, field: &str) {
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+
+    fn get_value(data_type: &DataType) -> String {
+        match data_type {
+            DataType::Null => String::new(),
+            DataType::Int(value) => value.to_string(),
+            DataType::Real(value) => format!("{:?}", value),
+            DataType::Text(value) | DataType::Symbol(value) => value.to_string(),
+        }
+    }
+
+    // public function
+    pub fn encode_to_string(response: &QueryResponse) -> String {
+        let mut csv = String::from("from,to,name,type,value\r\n");
+        if let QueryResponse::ResultSet(result_set) = response {
+            for row in result_set.get_rows() {
+                let logic_times = row.get_logic_times();
+                for (name, value) in result_set.get_columns().iter().zip(row.get_values()) {
+                    csv.push_str(&format!("{},{},", logic_times.start(), logic_times.end()));
+                    Self::write_field(&mut csv, name);
+                    csv.push(',');
+                    csv.push_str(get_type_name(value));
+                    csv.push(',');
+                    Self::write_field(&mut csv, &Self::get_value(value));
+                    csv.push_str("\r\n");
+                }
+            }
+        }
+        csv
+    }
+}
+
+impl ResponseEncoder for CsvEncoder {
+    fn get_name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn encode(&self, response: &QueryResponse) -> Vec<u8> {
+        Self::encode_to_string(response).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoding::csv_encoder::CsvEncoder;
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::query::query_resolver::QueryResponse;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_csv_encoder() {
+        let mut result_set = ResultSet::new(vec!["c".to_string(), "d".to_string()]);
+        result_set.push_row(Row::new(0..=1, vec![DataType::Null, DataType::Int(2)]));
+        result_set.push_row(Row::new(
+            2..=2,
+            vec![DataType::Real(2.0), DataType::Text("a,\"b\"".to_string())],
+        ));
+        result_set.push_row(Row::new(3..=3, vec![DataType::Symbol("e".to_string()), DataType::Null]));
+        debug_assert_eq!(
+            "from,to,name,type,value\r\n0,1,c,null,\r\n0,1,d,int,2\r\n2,2,c,real,2.0\r\n\
+             2,2,d,text,\"a,\"\"b\"\"\"\r\n3,3,c,symbol,e\r\n3,3,d,null,\r\n",
+            CsvEncoder::encode_to_string(&QueryResponse::ResultSet(result_set))
+        );
+        debug_assert_eq!(
+            "from,to,name,type,value\r\n",
+            CsvEncoder::encode_to_string(&QueryResponse::None)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::encoding::response_encoder::{get_type_name, ResponseEncoder};
+use crate::memory::result_set::ResultSet;
+use crate::query::query_resolver::QueryResponse;
+use qdb_ast::ast::types::DataType;
+
+// JSON of response:
+//     {"columns":["c","d"],"rows":[{"from":0,"to":1,"values":[{"type":"int","value":2},{"type":"null"}]}]}
+// response without result is null
+pub struct JsonEncoder;
+
+impl JsonEncoder {
+    // hidden function
+    fn write_string(json: &mut String, value: &str) {
+        json.push('"');
+        for symbol in value.chars() {
+            match symbol {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                _ if (symbol as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", symbol as u32)),
+                _ => json.push(symbol),
+            }
+        }
+        json.push('"');
+    }
+
+    fn write_value(json: &mut String, data_type: &DataType) {
+        json.push_str("{\"type\":");
+        Self::write_string(json, get_type_name(data_type));
+        match data_type {
+            DataType::Null => {}
+            DataType::Int(value) => json.push_str(&format!(",\"value\":{}", value)),
+            DataType::Real(value) if value.is_finite() => {
+                json.push_str(&format!(",\"value\":{:?}", value))
+            }
+            DataType::Real(value) => {
+                json.push_str(",\"value\":");
+                Self::write_string(json, &format!("{}", value));
+            }
+            DataType::Text(value) | DataType::Symbol(value) => {
+                json.push_str(",\"value\":");
+                Self::write_string(json, value);
+            }
+        }
+        json.push('}');
+    }
+
+    fn write_result_set(json: &mut String, result_set: &ResultSet) {
+        json.push_str("{\"columns\":[");
+        for (index, column) in result_set.get_columns().iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            Self::write_string(json, column);
+        }
+        json.push_str("],\"rows\":[");
+        for (index, row) in result_set.get_rows().iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let logic_times = row.get_logic_times();
+            json.push_str(&format!(
+                "{{\"from\":{},\"to\":{},\"values\":[",
+                logic_times.start(),
+                logic_times.end()
+            ));
+            for (index, value) in row.get_values().iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                Self::write_value(json, value);
+            }
+            json.push_str("]}");
+        }
+        json.push_str("]}");
+    }
+
+    // public function
+    pub fn encode_to_string(response: &QueryResponse) -> String {
+        let mut json = String::new();
+        match response {
+            QueryResponse::ResultSet(result_set) => Self::write_result_set(&mut json, result_set),
+            QueryResponse::Subscription(subscriber) => {
+                json.push_str(&format!("{{\"subscription\":{}}}", subscriber.get_id()))
+            }
+            QueryResponse::None => json.push_str("null"),
+        }
+        json
+    }
+}
+
+impl ResponseEncoder for JsonEncoder {
+    fn get_name(&self) -> &'static str {
+        "json"
+    }
+
+    fn encode(&self, response: &QueryResponse) -> Vec<u8> {
+        Self::encode_to_string(response).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoding::json_encoder::JsonEncoder;
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::query::query_resolver::QueryResponse;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_json_encoder() {
+        let mut result_set = ResultSet::new(vec!["c".to_string(), "d".to_string()]);
+        result_set.push_row(Row::new(
+            0..=1,
+            vec![DataType::Null, DataType::Text("a\"b\n".to_string())],
+        ));
+        result_set.push_row(Row::new(2..=2, vec![DataType::Int(-2), DataType::Symbol("e".to_string())]));
+        result_set.push_row(Row::new(3..=3, vec![DataType::Real(1.0), DataType::Real(f64::NAN)]));
+        debug_assert_eq!(
+            "{\"columns\":[\"c\",\"d\"],\"rows\":[\
+             {\"from\":0,\"to\":1,\"values\":[{\"type\":\"null\"},{\"type\":\"text\",\"value\":\"a\\\"b\\n\"}]},\
+             {\"from\":2,\"to\":2,\"values\":[{\"type\":\"int\",\"value\":-2},{\"type\":\"symbol\",\"value\":\"e\"}]},\
+             {\"from\":3,\"to\":3,\"values\":[{\"type\":\"real\",\"value\":1.0},{\"type\":\"real\",\"value\":\"NaN\"}]}]}",
+            JsonEncoder::encode_to_string(&QueryResponse::ResultSet(result_set))
+        );
+        debug_assert_eq!("null", JsonEncoder::encode_to_string(&QueryResponse::None));
+    }
+}
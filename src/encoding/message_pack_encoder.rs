@@ -0,0 +1,192 @@
+use crate::encoding::response_encoder::{get_type_name, ResponseEncoder};
+use crate::query::query_resolver::QueryResponse;
+use qdb_ast::ast::types::DataType;
+
+// MessagePack of response, same structure as JSON:
+//     {"columns": ["c", "d"], "rows": [{"from": 0, "to": 1, "values": [{"type": "int", "value": 2}, {"type": "null"}]}]}
+// integers use the smallest format, reals are float 64,
+// response without result is nil
+pub struct MessagePackEncoder;
+
+impl MessagePackEncoder {
+    // hidden function
+    fn write_int(bytes: &mut Vec<u8>, value: i64) {
+        match value {
+            0..=0x7f => bytes.push(value as u8),
+            -32..=-1 => bytes.push(value as i8 as u8),
+            0x80..=0xff => bytes.extend_from_slice(&[0xcc, value as u8]),
+            0x100..=0xffff => {
+                bytes.push(0xcd);
+                bytes.extend_from_slice(&(value as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                bytes.push(0xce);
+                bytes.extend_from_slice(&(value as u32).to_be_bytes());
+            }
+            -0x80..=-33 => bytes.extend_from_slice(&[0xd0, value as i8 as u8]),
+            -0x8000..=-0x81 => {
+                bytes.push(0xd1);
+                bytes.extend_from_slice(&(value as i16).to_be_bytes());
+            }
+            -0x8000_0000..=-0x8001 => {
+                bytes.push(0xd2);
+                bytes.extend_from_slice(&(value as i32).to_be_bytes());
+            }
+            _ if value > 0 => {
+                bytes.push(0xcf);
+                bytes.extend_from_slice(&(value as u64).to_be_bytes());
+            }
+            _ => {
+                bytes.push(0xd3);
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+
+    fn write_str(bytes: &mut Vec<u8>, value: &str) {
+        let len = value.len();
+        if len < 32 {
+            bytes.push(0xa0 | len as u8);
+        } else if len <= 0xff {
+            bytes.extend_from_slice(&[0xd9, len as u8]);
+        } else if len <= 0xffff {
+            bytes.push(0xda);
+            bytes.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            bytes.push(0xdb);
+            bytes.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+        bytes.extend_from_slice(value.as_bytes());
+    }
+
+    // header of array (0x90) or map (0x80)
+    fn write_len(bytes: &mut Vec<u8>, fix_tag: u8, len: usize) {
+        let is_array = fix_tag == 0x90;
+        if len < 16 {
+            bytes.push(fix_tag | len as u8);
+        } else if len <= 0xffff {
+            bytes.push(if is_array { 0xdc } else { 0xde });
+            bytes.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            bytes.push(if is_array { 0xdd } else { 0xdf });
+            bytes.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+
+    fn write_value(bytes: &mut Vec<u8>, data_type: &DataType) {
+        let len = if matches!(data_type, DataType::Null) { 1 } else { 2 };
+        Self::write_len(bytes, 0x80, len);
+        Self::write_str(bytes, "type");
+        Self::write_str(bytes, get_type_name(data_type));
+        if len == 1 {
+            return;
+        }
+        Self::write_str(bytes, "value");
+        match data_type {
+            DataType::Null => {}
+            DataType::Int(value) => Self::write_int(bytes, *value),
+            DataType::Real(value) => {
+                bytes.push(0xcb);
+                bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+            }
+            DataType::Text(value) | DataType::Symbol(value) => Self::write_str(bytes, value),
+        }
+    }
+}
+
+impl ResponseEncoder for MessagePackEncoder {
+    fn get_name(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn encode(&self, response: &QueryResponse) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let result_set = match response {
+            QueryResponse::ResultSet(result_set) => result_set,
+            QueryResponse::Subscription(subscriber) => {
+                Self::write_len(&mut bytes, 0x80, 1);
+                Self::write_str(&mut bytes, "subscription");
+                Self::write_int(&mut bytes, subscriber.get_id() as i64);
+                return bytes;
+            }
+            QueryResponse::None => {
+                bytes.push(0xc0);
+                return bytes;
+            }
+        };
+
+        Self::write_len(&mut bytes, 0x80, 2);
+        Self::write_str(&mut bytes, "columns");
+        Self::write_len(&mut bytes, 0x90, result_set.get_columns().len());
+        for column in result_set.get_columns() {
+            Self::write_str(&mut bytes, column);
+        }
+        Self::write_str(&mut bytes, "rows");
+        Self::write_len(&mut bytes, 0x90, result_set.get_rows().len());
+        for row in result_set.get_rows() {
+            Self::write_len(&mut bytes, 0x80, 3);
+            Self::write_str(&mut bytes, "from");
+            Self::write_int(&mut bytes, *row.get_logic_times().start());
+            Self::write_str(&mut bytes, "to");
+            Self::write_int(&mut bytes, *row.get_logic_times().end());
+            Self::write_str(&mut bytes, "values");
+            Self::write_len(&mut bytes, 0x90, row.get_values().len());
+            for value in row.get_values() {
+                Self::write_value(&mut bytes, value);
+            }
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoding::message_pack_encoder::MessagePackEncoder;
+    use crate::encoding::response_encoder::ResponseEncoder;
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::query::query_resolver::QueryResponse;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_message_pack_encoder() {
+        let mut bytes = Vec::new();
+        for value in &[0, 127, 128, -1, -32, -33, 65536, -129, i64::MIN] {
+            MessagePackEncoder::write_int(&mut bytes, *value);
+        }
+        debug_assert_eq!(
+            vec![
+                0x00, 0x7f, 0xcc, 0x80, 0xff, 0xe0, 0xd0, 0xdf, 0xce, 0x00, 0x01, 0x00, 0x00, 0xd1,
+                0xff, 0x7f, 0xd3, 0x80, 0, 0, 0, 0, 0, 0, 0
+            ],
+            bytes
+        );
+
+        let mut result_set = ResultSet::new(vec!["c".to_string(), "d".to_string()]);
+        result_set.push_row(Row::new(0..=1, vec![DataType::Null, DataType::Real(1.5)]));
+        let response = QueryResponse::ResultSet(result_set);
+        let mut expected = vec![0x82, 0xa7];
+        expected.extend_from_slice(b"columns");
+        expected.extend_from_slice(&[0x92, 0xa1, b'c', 0xa1, b'd', 0xa4]);
+        expected.extend_from_slice(b"rows");
+        expected.extend_from_slice(&[0x91, 0x83, 0xa4]);
+        expected.extend_from_slice(b"from");
+        expected.extend_from_slice(&[0x00, 0xa2]);
+        expected.extend_from_slice(b"to");
+        expected.extend_from_slice(&[0x01, 0xa6]);
+        expected.extend_from_slice(b"values");
+        expected.extend_from_slice(&[0x92, 0x81, 0xa4]);
+        expected.extend_from_slice(b"type");
+        expected.push(0xa4);
+        expected.extend_from_slice(b"null");
+        expected.extend_from_slice(&[0x82, 0xa4]);
+        expected.extend_from_slice(b"type");
+        expected.push(0xa4);
+        expected.extend_from_slice(b"real");
+        expected.push(0xa5);
+        expected.extend_from_slice(b"value");
+        expected.push(0xcb);
+        expected.extend_from_slice(&1.5f64.to_bits().to_be_bytes());
+        debug_assert_eq!(expected, MessagePackEncoder.encode(&response));
+        debug_assert_eq!(vec![0xc0], MessagePackEncoder.encode(&QueryResponse::None));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod csv_encoder;
+pub mod json_encoder;
+pub mod message_pack_encoder;
+pub mod response_encoder;
@@ -0,0 +1,38 @@
+use crate::encoding::csv_encoder::CsvEncoder;
+use crate::encoding::json_encoder::JsonEncoder;
+use crate::encoding::message_pack_encoder::MessagePackEncoder;
+use crate::query::query_resolver::QueryResponse;
+use qdb_ast::ast::types::DataType;
+
+// Encoder of query response for downstream services.
+// Every encoder maps value to pair of type name and value:
+//     Null   - "null", no value
+//     Int    - "int", integer
+//     Real   - "real", float (NaN and infinities are strings "NaN", "inf", "-inf" in JSON)
+//     Text   - "text", string
+//     Symbol - "symbol", string with name of symbol
+pub trait ResponseEncoder {
+    // name of format, e.g. "json"
+    fn get_name(&self) -> &'static str;
+    fn encode(&self, response: &QueryResponse) -> Vec<u8>;
+}
+
+pub fn get_type_name(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Null => "null",
+        DataType::Int(_) => "int",
+        DataType::Real(_) => "real",
+        DataType::Text(_) => "text",
+        DataType::Symbol(_) => "symbol",
+    }
+}
+
+// To get encoder by name of format: json, csv or msgpack
+pub fn get_encoder(name: &str) -> Option<Box<dyn ResponseEncoder>> {
+    match name {
+        "json" => Some(Box::new(JsonEncoder)),
+        "csv" => Some(Box::new(CsvEncoder)),
+        "msgpack" => Some(Box::new(MessagePackEncoder)),
+        _ => None,
+    }
+}
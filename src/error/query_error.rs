@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::fmt;
+
+// Error of query resolving. Returned instead of panic,
+// so one bad query can't take down the whole process.
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    // query line can't be parsed or has wrong structure
+    ParseError(String),
+    // channel not found in memory channel
+    UnknownChannel(String),
+    // variable not found in memory table
+    UnknownVariable(String),
+    // unqualified variable is found in several channels of query
+    AmbiguousVariable(String),
+    // operator is not supported by predicate
+    UnsupportedOperator(String),
+    // values can't be compared or stored together
+    TypeMismatch(String),
+    // log or snapshot can't be read or written
+    StorageError(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::ParseError(message) => write!(f, "parse error: {}", message),
+            QueryError::UnknownChannel(name) => write!(f, "unknown channel: {}", name),
+            QueryError::UnknownVariable(name) => write!(f, "unknown variable: {}", name),
+            QueryError::AmbiguousVariable(name) => write!(f, "ambiguous variable: {}, qualify it by channel", name),
+            QueryError::UnsupportedOperator(operator) => {
+                write!(f, "unsupported operator: {}", operator)
+            }
+            QueryError::TypeMismatch(message) => write!(f, "type mismatch: {}", message),
+            QueryError::StorageError(message) => write!(f, "storage error: {}", message),
+        }
+    }
+}
+
+impl Error for QueryError {}
@@ -1,10 +1,95 @@
 extern crate qdb_ast;
 
-use qdb_ast::parser::states;
-use qdb_ast::parser::states::DefaultParser;
-use std::borrow::Cow;
-
+pub mod encoding;
+pub mod error;
 pub mod memory;
 pub mod query;
+pub mod repl;
+pub mod server;
+pub mod storage;
+
+use repl::repl_session::ReplSession;
+use server::tcp_server::{ServerConfig, TcpServer};
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+const USAGE: &str = "usage:
+    qdb [serve] [--bind <address>] [--max-connections <count>] [--max-line-length <bytes>]
+              [--wal <path>] [--sync always|never|every:<count>]
+              [--snapshot <path>] [--checkpoint-every <count>]
+    qdb repl";
+
+fn parse_server_config(args: &[String]) -> Result<ServerConfig, String> {
+    let mut config = ServerConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bind" => {
+                config.bind_address = args.next().ok_or("--bind requires address")?.to_string();
+            }
+            "--max-connections" => {
+                let count = args.next().ok_or("--max-connections requires count")?;
+                config.max_connections = count
+                    .parse()
+                    .map_err(|_| format!("invalid max connections: {}", count))?;
+            }
+            "--max-line-length" => {
+                let length = args.next().ok_or("--max-line-length requires length")?;
+                config.max_line_length = length
+                    .parse()
+                    .map_err(|_| format!("invalid max line length: {}", length))?;
+            }
+            "--wal" => {
+                config.storage.wal_path = Some(args.next().ok_or("--wal requires path")?.into());
+            }
+            "--sync" => {
+                config.storage.sync_policy = args.next().ok_or("--sync requires policy")?.parse()?;
+            }
+            "--snapshot" => {
+                config.storage.snapshot_path =
+                    Some(args.next().ok_or("--snapshot requires path")?.into());
+            }
+            "--checkpoint-every" => {
+                let count = args.next().ok_or("--checkpoint-every requires count")?;
+                config.storage.checkpoint_records = count
+                    .parse()
+                    .map_err(|_| format!("invalid checkpoint count: {}", count))?;
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    Ok(config)
+}
+
+fn serve(args: &[String]) -> Result<(), String> {
+    let config = parse_server_config(args)?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    for signal in &[signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        signal_hook::flag::register(*signal, shutdown.clone()).map_err(|e| e.to_string())?;
+    }
+
+    let server = TcpServer::bind(config).map_err(|e| e.to_string())?;
+    let address = server.local_addr().map_err(|e| e.to_string())?;
+    eprintln!("qdb: listening on {}", address);
+    server.run(shutdown).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("serve") => serve(&args[1..]),
+        Some("repl") => ReplSession::new().run(),
+        Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => serve(&args),
+    };
 
-fn main() {}
+    if let Err(message) = result {
+        eprintln!("qdb: {}\n{}", message, USAGE);
+        process::exit(2);
+    }
+}
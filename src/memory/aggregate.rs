@@ -0,0 +1,68 @@
+// Aggregate functions over values of variable.
+// Every logic time where variable has value is one occurrence,
+// so value written once and kept for 3 logic times is counted 3 times.
+// Null values are skipped by all functions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    Count,
+    CountDistinct,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl AggregateFunction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(AggregateFunction::Count),
+            "count_distinct" => Some(AggregateFunction::CountDistinct),
+            "sum" => Some(AggregateFunction::Sum),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "avg" => Some(AggregateFunction::Avg),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::CountDistinct => "count_distinct",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Avg => "avg",
+        }
+    }
+}
+
+// Aggregate function applied to variable, e.g. max(a)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    function: AggregateFunction,
+    name: String,
+}
+
+impl Aggregate {
+    pub fn new(function: AggregateFunction, name: &str) -> Self {
+        Aggregate {
+            function,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn get_function(&self) -> AggregateFunction {
+        self.function
+    }
+
+    // To get name of variable
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    // To get name of result column, e.g. "max(a)"
+    pub fn get_label(&self) -> String {
+        format!("{}({})", self.function.get_name(), self.name)
+    }
+}
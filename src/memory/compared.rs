@@ -1,4 +1,3 @@
-use qdb_ast::ast::types::DataType;
 use std::cmp::Ordering;
 
 /// Compared - trait for specific comparison inside used type
@@ -22,9 +21,7 @@ pub trait Compared {
     fn compare_with_override(&self, other: Self) -> Option<Ordering>;
 
     fn comparing<F: Fn(&Ordering) -> bool, T: Compared>(left: T, right: T, predicate: F) -> bool {
-        let maybe_order_result = left.compare_with_override(right);
-        if maybe_order_result.is_some() {
-            let order_result = maybe_order_result.unwrap();
+        if let Some(order_result) = left.compare_with_override(right) {
             return predicate(&order_result);
         }
         false
@@ -32,51 +29,31 @@ pub trait Compared {
     // ==
     fn eq(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Equal => true,
-            _ => false,
-        };
+        matches!(ord, Ordering::Equal)
     }
     // !=
     fn neq(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Equal => false,
-            _ => true,
-        };
+        !matches!(ord, Ordering::Equal)
     }
     // >=
     fn eq_or_gr(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Equal => true,
-            Ordering::Greater => true,
-            _ => false,
-        };
+        matches!(ord, Ordering::Equal | Ordering::Greater)
     }
     // >
     fn gr(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Greater => true,
-            _ => false,
-        };
+        matches!(ord, Ordering::Greater)
     }
     // <=
     fn eq_or_le(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Equal => true,
-            Ordering::Less => true,
-            _ => false,
-        };
+        matches!(ord, Ordering::Equal | Ordering::Less)
     }
     // <
     fn le(ord: &Ordering) -> bool {
         use std::cmp::Ordering;
-        return match ord {
-            Ordering::Less => true,
-            _ => false,
-        };
+        matches!(ord, Ordering::Less)
     }
 }
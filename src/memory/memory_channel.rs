@@ -1,4 +1,45 @@
 use crate::memory::memory_table::MemoryTable;
+use crate::error::query_error::QueryError;
 use rbtree::RBTree;
 
 pub type MemoryChannel = RBTree<String, MemoryTable>;
+
+// Access of query to tables of channels.
+// It's implemented by MemoryChannel and by locked tables of concurrent channel,
+// which gives only tables of query channels.
+// Names are &String as keys of RBTree of MemoryChannel.
+#[allow(clippy::ptr_arg)]
+pub trait ChannelAccess {
+    fn get_table(&self, channel_name: &String) -> Option<&MemoryTable>;
+
+    // None if table isn't locked for write
+    fn get_table_mut(&mut self, channel_name: &String) -> Option<&mut MemoryTable>;
+
+    // To get table of channel, unknown channel is created
+    fn get_or_create_table(&mut self, channel_name: &String) -> Result<&mut MemoryTable, QueryError>;
+
+    // To get tables in order of channel names
+    fn get_tables(&self) -> Vec<(&String, &MemoryTable)>;
+}
+
+impl ChannelAccess for MemoryChannel {
+    fn get_table(&self, channel_name: &String) -> Option<&MemoryTable> {
+        self.get(channel_name)
+    }
+
+    fn get_table_mut(&mut self, channel_name: &String) -> Option<&mut MemoryTable> {
+        self.get_mut(channel_name)
+    }
+
+    fn get_or_create_table(&mut self, channel_name: &String) -> Result<&mut MemoryTable, QueryError> {
+        if !self.contains_key(channel_name) {
+            self.insert(channel_name.to_string(), MemoryTable::init());
+        }
+        self.get_mut(channel_name)
+            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))
+    }
+
+    fn get_tables(&self) -> Vec<(&String, &MemoryTable)> {
+        self.iter().collect()
+    }
+}
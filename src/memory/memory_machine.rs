@@ -1,17 +1,291 @@
+use crate::memory::aggregate::AggregateFunction;
 use crate::memory::compared::Compared;
 use crate::memory::intersection::Intersection;
+use crate::memory::schema::ValueType;
+use crate::error::query_error::QueryError;
 use qdb_ast::ast::types::DataType;
-use rbtree::{Iter, RBTree};
-use std::borrow::{BorrowMut, Cow};
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::mem::size_of;
 use std::ops::RangeInclusive;
 
-pub type Indexes = Vec<RangeInclusive<i64>>;
+// Normalised set of logic time indexes:
+// ranges are sorted, not empty and never overlap or touch each other.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IntervalSet {
+    ranges: Vec<RangeInclusive<i64>>,
+}
+
+impl IntervalSet {
+    // hidden function
+    fn normalize(mut ranges: Vec<RangeInclusive<i64>>) -> Vec<RangeInclusive<i64>> {
+        ranges.retain(|range| !range.is_empty());
+        ranges.sort_by_key(|range| *range.start());
+
+        let mut normalized: Vec<RangeInclusive<i64>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            if let Some(last) = normalized.last_mut() {
+                if *range.start() <= last.end().saturating_add(1) {
+                    if range.end() > last.end() {
+                        *last = RangeInclusive::new(*last.start(), *range.end());
+                    }
+                    continue;
+                }
+            }
+            normalized.push(range);
+        }
+        normalized
+    }
+
+    // public function
+    pub fn new() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    pub fn from_range(range: RangeInclusive<i64>) -> Self {
+        let mut interval_set = IntervalSet::new();
+        interval_set.insert(range);
+        interval_set
+    }
+
+    // To add range to set. Appending after last range is O(1).
+    pub fn insert(&mut self, range: RangeInclusive<i64>) {
+        if range.is_empty() {
+            return;
+        }
+        if let Some(last) = self.ranges.last_mut() {
+            if *range.start() > last.end().saturating_add(1) {
+                self.ranges.push(range);
+                return;
+            }
+            if range.start() >= last.start() {
+                if range.end() > last.end() {
+                    *last = RangeInclusive::new(*last.start(), *range.end());
+                }
+                return;
+            }
+        }
+        self.ranges.push(range);
+        self.ranges = Self::normalize(std::mem::take(&mut self.ranges));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // To remove indexes before index, ranges after it aren't visited
+    pub fn remove_before(&mut self, index: i64) {
+        let position = self.ranges.partition_point(|range| *range.end() < index);
+        self.ranges.drain(..position);
+        if let Some(first) = self.ranges.first_mut() {
+            if *first.start() < index {
+                *first = RangeInclusive::new(index, *first.end());
+            }
+        }
+    }
+
+    // To get count of ranges
+    pub fn ranges_count(&self) -> usize {
+        self.ranges.len()
+    }
+
+    // To get count of indexes in all ranges
+    pub fn indexes_count(&self) -> i64 {
+        self.ranges
+            .iter()
+            .map(|range| range.end() - range.start() + 1)
+            .sum()
+    }
+
+    // To get first index of set
+    pub fn start(&self) -> Option<i64> {
+        self.ranges.first().map(|range| *range.start())
+    }
+
+    // To get last index of set
+    pub fn end(&self) -> Option<i64> {
+        self.ranges.last().map(|range| *range.end())
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, RangeInclusive<i64>> {
+        self.ranges.iter()
+    }
+
+    // a ∈ A
+    pub fn contains(&self, index: i64) -> bool {
+        self.get_range(index).is_some()
+    }
+
+    // To get range of set which contains index
+    pub fn get_range(&self, index: i64) -> Option<&RangeInclusive<i64>> {
+        self.ranges
+            .binary_search_by(|range| {
+                if *range.end() < index {
+                    Ordering::Less
+                } else if *range.start() > index {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()
+            .map(|position| &self.ranges[position])
+    }
+
+    // B ⊆ A
+    pub fn contains_set(&self, other: &IntervalSet) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    // A ⋃ B
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = self.ranges.clone();
+        ranges.extend(other.ranges.iter().cloned());
+        IntervalSet {
+            ranges: Self::normalize(ranges),
+        }
+    }
+
+    // A ⋂ B
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let (left, right) = (&self.ranges, &other.ranges);
+        let mut ranges: Vec<RangeInclusive<i64>> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            let start = *left[i].start().max(right[j].start());
+            let end = *left[i].end().min(right[j].end());
+            if start <= end {
+                ranges.push(RangeInclusive::new(start, end));
+            }
+            if left[i].end() < right[j].end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    // A \ B
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        match (self.start(), self.end()) {
+            (Some(start), Some(end)) => {
+                self.intersection(&other.complement(&RangeInclusive::new(start, end)))
+            }
+            _ => IntervalSet::new(),
+        }
+    }
+
+    // U \ A, where U - bounds
+    pub fn complement(&self, bounds: &RangeInclusive<i64>) -> IntervalSet {
+        let mut ranges: Vec<RangeInclusive<i64>> = Vec::new();
+        if bounds.is_empty() {
+            return IntervalSet { ranges };
+        }
+
+        let mut next_start = *bounds.start();
+        for range in self.intersection(&IntervalSet::from_range(bounds.clone())).iter() {
+            if *range.start() > next_start {
+                ranges.push(RangeInclusive::new(next_start, range.start() - 1));
+            }
+            next_start = range.end() + 1;
+        }
+        if next_start <= *bounds.end() {
+            ranges.push(RangeInclusive::new(next_start, *bounds.end()));
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl From<Vec<RangeInclusive<i64>>> for IntervalSet {
+    fn from(ranges: Vec<RangeInclusive<i64>>) -> Self {
+        IntervalSet {
+            ranges: Self::normalize(ranges),
+        }
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = RangeInclusive<i64>>>(iter: T) -> Self {
+        IntervalSet::from(iter.into_iter().collect::<Vec<RangeInclusive<i64>>>())
+    }
+}
+
+impl IntoIterator for IntervalSet {
+    type Item = RangeInclusive<i64>;
+    type IntoIter = std::vec::IntoIter<RangeInclusive<i64>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IntervalSet {
+    type Item = &'a RangeInclusive<i64>;
+    type IntoIter = std::slice::Iter<'a, RangeInclusive<i64>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter()
+    }
+}
+
+// Key of tree map of values. Order of keys is defined here and doesn't depend
+// on Ord of DataType: values are grouped by kind (null, number, text, symbol),
+// int and real numbers are one group ordered by value, so seek of range predicate
+// and min/max see numbers of both types in one scan. Equal numbers of different
+// types (3 and 3.0) are distinct keys, int goes first.
+#[derive(Debug, Clone)]
+struct ValueKey(DataType);
+
+impl ValueKey {
+    fn get_group(&self) -> u8 {
+        match self.0 {
+            DataType::Null => 0,
+            DataType::Int(_) | DataType::Real(_) => 1,
+            DataType::Text(_) => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl Ord for ValueKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ordering = match (&self.0, &other.0) {
+            (DataType::Int(this), DataType::Real(other)) => (*this as f64).total_cmp(other).then(Ordering::Less),
+            (DataType::Real(this), DataType::Int(other)) => this.total_cmp(&(*other as f64)).then(Ordering::Greater),
+            (DataType::Real(this), DataType::Real(other)) => this.total_cmp(other),
+            (this, other) => this.cmp(other),
+        };
+        self.get_group().cmp(&other.get_group()).then(ordering)
+    }
+}
+
+impl PartialOrd for ValueKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ValueKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ValueKey {}
 
+// BTreeMap is used as tree map instead of RBTree: range predicates seek
+// to operand by range of keys, which RBTree doesn't provide.
+// timeline - start of each continuous range of value, to find value
+// at logic time without scan of tree map
 #[derive(Debug)]
 pub struct MemoryMachine {
-    mem: RBTree<DataType, Indexes>,
+    mem: BTreeMap<ValueKey, IntervalSet>,
+    timeline: BTreeMap<i64, ValueKey>,
+    deleted: IntervalSet,
+    last_value: Option<ValueKey>,
     logic_time: i64,
 }
 
@@ -20,101 +294,571 @@ impl MemoryMachine {
     // and logic time equal 0 (original number).
     pub fn init() -> Self {
         MemoryMachine {
-            mem: RBTree::new(),
+            mem: BTreeMap::new(),
+            timeline: BTreeMap::new(),
+            deleted: IntervalSet::new(),
+            last_value: None,
             logic_time: 0,
         }
     }
 
-    // To insert data_type in tree map. Where key - data_type value, value - vec indexes.
+    // To insert data_type in tree map at own logic time.
+    // Where key - data_type value, value - set of indexes.
     pub fn insert(&mut self, data_type: DataType) {
+        let logic_time = self.logic_time;
+        self.insert_at(data_type, logic_time);
+    }
+
+    // To insert data_type in tree map at given logic time (row clock of table).
+    // Previous value is carried forward until this logic time.
+    pub fn insert_at(&mut self, data_type: DataType, logic_time: i64) {
+        self.extend_to(logic_time - 1);
+
+        let data_type = ValueKey(data_type);
         let key = self.mem.get(&data_type);
 
+        let is_continued = self.last_value.as_ref() == Some(&data_type)
+            && matches!(key, Some(interval_set) if interval_set.contains(logic_time - 1));
+        if !is_continued {
+            self.timeline.insert(logic_time, data_type.clone());
+        }
+
         if key.is_none() {
             self.mem.insert(
-                data_type,
-                vec![RangeInclusive::new(self.logic_time, self.logic_time)],
+                data_type.clone(),
+                IntervalSet::from_range(RangeInclusive::new(logic_time, logic_time)),
             );
         } else {
-            let logic_time = self.logic_time.clone();
-            let vec = self.mem.get_mut(&data_type).unwrap();
-            let maybe_range = vec.iter_mut().find(|e| e.contains(&(logic_time - 1)));
+            // adjacent range of same value is merged by interval set
+            let interval_set = self.mem.get_mut(&data_type).unwrap();
+            interval_set.insert(RangeInclusive::new(logic_time, logic_time));
+        }
+        self.last_value = Some(data_type);
+        self.logic_time = logic_time + 1;
+    }
 
-            if maybe_range.is_some() {
-                let range = maybe_range.unwrap();
-                *range = RangeInclusive::new(*range.start(), self.logic_time);
-            } else {
-                vec.push(RangeInclusive::new(logic_time, logic_time));
+    // To carry last value forward until logic time (inclusive),
+    // so value stays current while other variables of the row are written.
+    pub fn extend_to(&mut self, logic_time: i64) {
+        if logic_time < self.logic_time {
+            return;
+        }
+
+        let last_index = self.logic_time - 1;
+        if self.is_deleted(last_index) {
+            // deleted value is not current anymore
+            self.last_value = None;
+        } else if let Some(last_value) = self.last_value.as_ref() {
+            if let Some(interval_set) = self.mem.get_mut(last_value) {
+                if interval_set.contains(last_index) {
+                    interval_set.insert(RangeInclusive::new(last_index, logic_time));
+                }
             }
         }
-        self.logic_time += 1;
+        self.logic_time = logic_time + 1;
     }
 
     // To get indexes by data_type value key from tree map.
-    pub fn get(&self, data_type: &DataType) -> Option<Indexes> {
-        Some(self.mem.get(&data_type).unwrap().clone())
+    pub fn get(&self, data_type: &DataType) -> Option<IntervalSet> {
+        self.mem.get(&ValueKey(data_type.clone())).cloned()
     }
 
     // To get data_type by indexes from tree map.
-    pub fn get_values_by_range_inclusive(&self, range_inclusive: &Indexes) -> Vec<DataType> {
+    // Tombstoned indexes are not visible.
+    pub fn get_values_by_range_inclusive(&self, range_inclusive: &IntervalSet) -> Vec<DataType> {
         let mut vec: Vec<DataType> = Vec::new();
         for (value, key) in self.mem.iter() {
-            if Vec::intersect(range_inclusive, key) {
-                vec.push(value.clone());
+            let live_key = self.get_live_indexes(key);
+            if IntervalSet::intersect(range_inclusive, &live_key) {
+                vec.push(value.0.clone());
             }
         }
-        return vec;
+        vec
     }
 
     // To get last value from memory machine
     pub fn get_last_value(&self) -> Option<&DataType> {
         let last_index = self.logic_time - 1;
-        for (symbol, indexes) in self.mem.iter() {
-            for range in indexes {
-                if range.contains(&last_index) {
-                    return Some(symbol);
+        if self.is_deleted(last_index) {
+            return None;
+        }
+        let last_value = self.last_value.as_ref()?;
+        let indexes = self.mem.get(last_value)?;
+        if indexes.contains(last_index) {
+            return Some(&last_value.0);
+        }
+        None
+    }
+
+    // To get value which is current at logic time
+    pub fn get_value_at(&self, logic_time: i64) -> Option<&DataType> {
+        if self.is_deleted(logic_time) {
+            return None;
+        }
+        let (start, value) = self.timeline.range(..=logic_time).next_back()?;
+        let range = self.mem.get(value)?.get_range(*start)?;
+        if range.contains(&logic_time) {
+            return Some(&value.0);
+        }
+        None
+    }
+
+    // To get history of values inside indexes in order of logic time.
+    // Where each element - range of logic time and value current in it.
+    pub fn get_history(&self, indexes: &IntervalSet) -> Vec<(RangeInclusive<i64>, DataType)> {
+        let mut vec: Vec<(RangeInclusive<i64>, DataType)> = Vec::new();
+        let (start, end) = match (indexes.start(), indexes.end()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return vec,
+        };
+
+        // first range can begin before indexes
+        let first = self
+            .timeline
+            .range(..=start)
+            .next_back()
+            .map_or(start, |(first, _)| *first);
+        for (range_start, value) in self.timeline.range(first..=end) {
+            let maybe_range = self
+                .mem
+                .get(value)
+                .and_then(|interval_set| interval_set.get_range(*range_start));
+            if let Some(range) = maybe_range {
+                let visible = IntervalSet::from_range(range.clone()).intersection(indexes);
+                for visible_range in self.get_live_indexes(&visible) {
+                    vec.push((visible_range, value.0.clone()));
                 }
             }
         }
-        None
+        vec
     }
 
     // To get vector of indexes filter by predicate from tree map
     // where f(a,b) := a x b, where x ∃ {==,!=,>=,>,<=,<}
     // then ∀a ∈ A
+    // Returns TypeMismatch if no one value can be compared with other.
     pub fn get_by_compare_with<F: Fn(&DataType, &DataType) -> bool>(
         &self,
         other: &DataType,
         predicate: F,
-    ) -> Vec<&Indexes> {
-        let mut vec: Vec<&Indexes> = vec![];
-        for i in self.mem.iter() {
-            if predicate(i.0, other) {
-                vec.push(i.1);
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        let mut vec: Vec<IntervalSet> = vec![];
+        let mut is_comparable = false;
+        let mut has_values = false;
+        for (key, indexes) in self.mem.iter() {
+            if key.0 != DataType::Null {
+                has_values = true;
+                is_comparable |= key.0.compare_with(other).is_some();
+            }
+            if predicate(&key.0, other) {
+                let live_indexes = self.get_live_indexes(indexes);
+                if !live_indexes.is_empty() {
+                    vec.push(live_indexes);
+                }
             }
         }
-        vec
+
+        if has_values && !is_comparable && *other != DataType::Null {
+            return Err(QueryError::TypeMismatch(format!(
+                "value {:?} can't be compared with stored values",
+                other
+            )));
+        }
+        Ok(vec)
     }
-}
 
-impl Intersection for Indexes {
-    // intersection in each element in vec
-    // if a ∈ A && a ∈ B => A ⋂ B
-    fn intersect(left: &Indexes, right: &Indexes) -> bool {
-        let left_vec_len = left.len() - 1;
-        let right_vec_len = right.len() - 1;
+    // To get vector of indexes of values a where ordering of a to other
+    // is accepted by predicate, ordering ∃ {<, ==, >}.
+    // Values comparable with other are one group of keys in tree map, so
+    // == scans only keys equal to other, range predicate seeks to other
+    // and scans the group in direction of accepted values,
+    // predicate which accepts both < and > scans whole tree map.
+    // Returns TypeMismatch if no one value can be compared with other.
+    pub fn get_by_ordering<F: Fn(&Ordering) -> bool>(
+        &self,
+        other: &DataType,
+        predicate: F,
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        let is_less = predicate(&Ordering::Less);
+        let is_greater = predicate(&Ordering::Greater);
+        if is_less && is_greater {
+            return self.get_by_compare_with(other, |this, other| DataType::comparing(this, other, &predicate));
+        }
+
+        let seek_key = ValueKey(other.clone());
+        let is_block = |(key, _): &(&ValueKey, &IntervalSet)| key.get_group() == seek_key.get_group();
+        let is_equal = |(key, _): &(&ValueKey, &IntervalSet)| key.0.compare_with(other) == Some(Ordering::Equal);
+        let before = self.mem.range(..&seek_key).rev();
+        let after = self.mem.range(&seek_key..);
+        let is_comparable =
+            before.clone().next().filter(is_block).is_some() || after.clone().next().filter(is_block).is_some();
+
+        // equal values of other type (3.0 for 3) lie on both sides of seek key
+        let keys: Vec<(&ValueKey, &IntervalSet)> = if is_greater {
+            before.take_while(is_equal).chain(after.take_while(is_block)).collect()
+        } else if is_less {
+            after.take_while(is_equal).chain(before.take_while(is_block)).collect()
+        } else {
+            before.take_while(is_equal).chain(after.take_while(is_equal)).collect()
+        };
+
+        let mut vec: Vec<IntervalSet> = vec![];
+        for (key, indexes) in keys {
+            if matches!(key.0.compare_with(other), Some(ordering) if predicate(&ordering)) {
+                let live_indexes = self.get_live_indexes(indexes);
+                if !live_indexes.is_empty() {
+                    vec.push(live_indexes);
+                }
+            }
+        }
+
+        let has_values = self.mem.keys().any(|key| key.0 != DataType::Null);
+        if has_values && !is_comparable && *other != DataType::Null {
+            return Err(QueryError::TypeMismatch(format!(
+                "value {:?} can't be compared with stored values",
+                other
+            )));
+        }
+        Ok(vec)
+    }
+
+    // To get indexes inside indexes where value of this machine relates to value
+    // of other machine at same logic time by ordering accepted by predicate.
+    // Histories of both machines are in order of logic time, so their segments
+    // are merged in one pass. Logic time where any value is missing or null isn't matched.
+    // Returns TypeMismatch if no one pair of values can be compared.
+    pub fn get_by_ordering_with<F: Fn(&Ordering) -> bool>(
+        &self,
+        other: &MemoryMachine,
+        indexes: &IntervalSet,
+        predicate: F,
+    ) -> Result<IntervalSet, QueryError> {
+        let history = self.get_history(indexes);
+        let other_history = other.get_history(indexes);
+        let mut result = IntervalSet::new();
+        let mut has_values = false;
+        let mut is_comparable = false;
+        let (mut position, mut other_position) = (0, 0);
+        while position < history.len() && other_position < other_history.len() {
+            let (range, value) = &history[position];
+            let (other_range, other_value) = &other_history[other_position];
+            let start = *range.start().max(other_range.start());
+            let end = *range.end().min(other_range.end());
+            if start <= end && *value != DataType::Null && *other_value != DataType::Null {
+                has_values = true;
+                if let Some(ordering) = value.compare_with(other_value) {
+                    is_comparable = true;
+                    if predicate(&ordering) {
+                        result.insert(start..=end);
+                    }
+                }
+            }
+            // segment which ends first has no more overlaps
+            if range.end() < other_range.end() {
+                position += 1;
+            } else {
+                other_position += 1;
+            }
+        }
+
+        if has_values && !is_comparable {
+            return Err(QueryError::TypeMismatch(
+                "values of variables can't be compared".to_string(),
+            ));
+        }
+        Ok(result)
+    }
+
+    // To mark indexes as deleted. Values stay in tree map (history is kept),
+    // but tombstoned indexes are hidden from reads.
+    pub fn delete(&mut self, indexes: &IntervalSet) {
+        self.deleted = self.deleted.union(indexes);
+    }
+
+    // To check that index was tombstoned by delete
+    pub fn is_deleted(&self, index: i64) -> bool {
+        self.deleted.contains(index)
+    }
+
+    // To drop indexes from history, then compact it
+    pub fn forget(&mut self, indexes: &IntervalSet) {
+        for interval_set in self.mem.values_mut() {
+            *interval_set = interval_set.difference(indexes);
+        }
+        self.compact();
+    }
+
+    // To drop history before logic time. Only ranges which start before it are visited,
+    // so it's cheap to call on every write.
+    pub fn forget_before(&mut self, logic_time: i64) {
+        let starts: Vec<i64> = self.timeline.range(..logic_time).map(|(start, _)| *start).collect();
+        for start in starts {
+            let value = match self.timeline.remove(&start) {
+                Some(value) => value,
+                None => continue,
+            };
+            let interval_set = match self.mem.get_mut(&value) {
+                Some(interval_set) => interval_set,
+                None => continue,
+            };
+            interval_set.remove_before(logic_time);
+            if interval_set.is_empty() {
+                self.remove_value(&value);
+            } else if interval_set.start() == Some(logic_time) {
+                // range which goes on after logic time starts at it
+                self.timeline.insert(logic_time, value);
+            }
+        }
+        self.deleted.remove_before(logic_time);
+    }
+
+    // To keep history only of last values, where last value - value with latest index.
+    // Values with earliest last index are dropped one by one, history of kept values isn't visited.
+    pub fn retain_last_values(&mut self, count: usize) {
+        while self.mem.len() > count {
+            let oldest = self
+                .mem
+                .iter()
+                .min_by_key(|(_, interval_set)| interval_set.end())
+                .map(|(value, _)| value.clone());
+            match oldest {
+                Some(value) => self.remove_value(&value),
+                None => break,
+            }
+        }
+    }
+
+    // To apply tombstones to history: deleted indexes are removed from values,
+    // values without indexes are removed from tree map and timeline is rebuilt
+    // from ranges of values, so it has no starts of dropped or merged ranges.
+    pub fn compact(&mut self) {
+        let deleted = std::mem::take(&mut self.deleted);
+        for interval_set in self.mem.values_mut() {
+            if !deleted.is_empty() {
+                *interval_set = interval_set.difference(&deleted);
+            }
+            interval_set.ranges.shrink_to_fit();
+        }
+        self.mem.retain(|_, interval_set| !interval_set.is_empty());
+
+        self.timeline.clear();
+        for (data_type, interval_set) in self.mem.iter() {
+            for range in interval_set.iter() {
+                self.timeline.insert(*range.start(), data_type.clone());
+            }
+        }
+
+        let last_index = self.logic_time - 1;
+        let last_interval_set = self.last_value.as_ref().and_then(|last_value| self.mem.get(last_value));
+        let is_last_value_kept = matches!(last_interval_set, Some(interval_set) if interval_set.contains(last_index));
+        if !is_last_value_kept {
+            self.last_value = None;
+        }
+    }
+
+    // To remove value with its history and its starts of ranges in timeline
+    fn remove_value(&mut self, value: &ValueKey) {
+        if let Some(interval_set) = self.mem.remove(value) {
+            for range in interval_set.iter() {
+                if self.timeline.get(range.start()) == Some(value) {
+                    self.timeline.remove(range.start());
+                }
+            }
+        }
+        if self.last_value.as_ref() == Some(value) {
+            self.last_value = None;
+        }
+    }
+
+    // To get indexes without tombstoned parts
+    // where A \ D, D - deleted indexes
+    fn get_live_indexes(&self, indexes: &IntervalSet) -> IntervalSet {
+        if self.deleted.is_empty() {
+            return indexes.clone();
+        }
+        indexes.difference(&self.deleted)
+    }
 
-        if left_vec_len + 1 > 0 && right_vec_len + 1 > 0 {
-            let left_range_start = left.get(0).unwrap();
-            let left_range_end = left.get(left_vec_len).unwrap();
+    // To get count of live logic times of value inside indexes
+    fn get_occurrences(&self, interval_set: &IntervalSet, indexes: &IntervalSet) -> i64 {
+        self.get_live_indexes(&interval_set.intersection(indexes))
+            .indexes_count()
+    }
 
-            let right_range_start = right.get(0).unwrap();
-            let right_range_end = right.get(right_vec_len).unwrap();
+    // To aggregate values at indexes, result is Null if there are no values.
+    // Min, max and counts are answered by tree map keys and their ranges,
+    // history is not materialised.
+    // Returns TypeMismatch if sum or avg meets not a number.
+    pub fn aggregate(
+        &self,
+        function: AggregateFunction,
+        indexes: &IntervalSet,
+    ) -> Result<DataType, QueryError> {
+        let mut occurrences = self
+            .mem
+            .iter()
+            .filter(|(data_type, _)| !matches!(data_type.0, DataType::Null))
+            .map(|(data_type, interval_set)| (&data_type.0, self.get_occurrences(interval_set, indexes)))
+            .filter(|(_, count)| *count > 0);
 
-            /*println!("lrs {:?} : lre {:?}",left_range_start,left_range_end);
-            println!("rrs {:?} : rre {:?}",right_range_start,right_range_end);*/
+        let (int_sum, real_sum, count, is_real) = match function {
+            AggregateFunction::Count => {
+                return Ok(DataType::Int(occurrences.map(|(_, count)| count).sum()));
+            }
+            AggregateFunction::CountDistinct => return Ok(DataType::Int(occurrences.count() as i64)),
+            AggregateFunction::Min => {
+                return Ok(occurrences
+                    .next()
+                    .map_or(DataType::Null, |(data_type, _)| data_type.clone()));
+            }
+            AggregateFunction::Max => {
+                let maybe_max = self.mem.iter().rev().find(|(data_type, interval_set)| {
+                    !matches!(data_type.0, DataType::Null)
+                        && self.get_occurrences(interval_set, indexes) > 0
+                });
+                return Ok(maybe_max.map_or(DataType::Null, |(data_type, _)| data_type.0.clone()));
+            }
+            AggregateFunction::Sum | AggregateFunction::Avg => {
+                let (mut int_sum, mut real_sum, mut count, mut is_real) = (0i128, 0f64, 0i64, false);
+                for (data_type, occurrences) in occurrences {
+                    match data_type {
+                        DataType::Int(value) => int_sum += *value as i128 * occurrences as i128,
+                        DataType::Real(value) => {
+                            real_sum += value * occurrences as f64;
+                            is_real = true;
+                        }
+                        _ => {
+                            return Err(QueryError::TypeMismatch(format!(
+                                "{} requires numbers, found {:?}",
+                                function.get_name(),
+                                data_type
+                            )))
+                        }
+                    }
+                    count += occurrences;
+                }
+                (int_sum, real_sum, count, is_real)
+            }
+        };
+
+        if count == 0 {
+            return Ok(DataType::Null);
+        }
+        let sum = int_sum as f64 + real_sum;
+        if function == AggregateFunction::Avg {
+            return Ok(DataType::Real(sum / count as f64));
+        }
+        // sum of integers which doesn't fit i64 is real
+        match i64::try_from(int_sum) {
+            Ok(int_sum) if !is_real => Ok(DataType::Int(int_sum)),
+            _ => Ok(DataType::Real(sum)),
+        }
+    }
+
+    // To get names of types of values stored by machine
+    pub fn get_type_names(&self) -> Vec<&'static str> {
+        let mut type_names: Vec<&'static str> = Vec::new();
+        for value_type in self.mem.keys().filter_map(|data_type| ValueType::of(&data_type.0)) {
+            let type_name = value_type.get_name();
+            if !type_names.contains(&type_name) {
+                type_names.push(type_name);
+            }
+        }
+        type_names
+    }
+
+    // To get count of distinct values without null
+    pub fn get_distinct_count(&self) -> usize {
+        self.mem.keys().filter(|data_type| data_type.0 != DataType::Null).count()
+    }
+
+    // To get count of continuous ranges of values in history
+    pub fn get_ranges_count(&self) -> usize {
+        self.timeline.len()
+    }
+
+    // To estimate bytes used by machine: tree maps, ranges and text of values
+    pub fn get_memory_usage(&self) -> usize {
+        let get_value_usage = |data_type: &ValueKey| match &data_type.0 {
+            DataType::Text(value) | DataType::Symbol(value) => size_of::<DataType>() + value.capacity(),
+            _ => size_of::<DataType>(),
+        };
+        let get_ranges_usage =
+            |interval_set: &IntervalSet| interval_set.ranges.capacity() * size_of::<RangeInclusive<i64>>();
+
+        let mut memory_usage = size_of::<MemoryMachine>() + get_ranges_usage(&self.deleted);
+        for (data_type, interval_set) in self.mem.iter() {
+            memory_usage += get_value_usage(data_type) + size_of::<IntervalSet>() + get_ranges_usage(interval_set);
+        }
+        for data_type in self.timeline.values() {
+            memory_usage += size_of::<i64>() + get_value_usage(data_type);
+        }
+        memory_usage
+    }
+
+    // To get logic time which is assigned to next value
+    pub fn get_logic_time(&self) -> i64 {
+        self.logic_time
+    }
+
+    // To get last inserted value, even if it isn't current anymore
+    pub fn get_last_inserted(&self) -> Option<&DataType> {
+        self.last_value.as_ref().map(|last_value| &last_value.0)
+    }
+
+    // To get values of tree map with their indexes
+    pub fn get_values(&self) -> impl Iterator<Item = (&DataType, &IntervalSet)> {
+        self.mem.iter().map(|(data_type, indexes)| (&data_type.0, indexes))
+    }
+
+    // To get starts of continuous ranges of values in order of logic time
+    pub fn get_timeline(&self) -> impl Iterator<Item = (i64, &DataType)> {
+        self.timeline.iter().map(|(logic_time, data_type)| (*logic_time, &data_type.0))
+    }
+
+    // To get tombstoned indexes which aren't compacted yet
+    pub fn get_deleted(&self) -> &IntervalSet {
+        &self.deleted
+    }
+
+    // To restore machine from its full state, e.g. read from snapshot
+    pub fn restore(
+        logic_time: i64,
+        last_value: Option<DataType>,
+        values: Vec<(DataType, IntervalSet)>,
+        timeline: Vec<(i64, DataType)>,
+        deleted: IntervalSet,
+    ) -> Self {
+        MemoryMachine {
+            mem: values
+                .into_iter()
+                .map(|(data_type, indexes)| (ValueKey(data_type), indexes))
+                .collect(),
+            timeline: timeline
+                .into_iter()
+                .map(|(logic_time, data_type)| (logic_time, ValueKey(data_type)))
+                .collect(),
+            deleted,
+            last_value: last_value.map(ValueKey),
+            logic_time,
+        }
+    }
+}
 
-            return RangeInclusive::intersect(left_range_start, right_range_start)
-                || RangeInclusive::intersect(left_range_end, right_range_end);
+impl Intersection for IntervalSet {
+    // intersection in each range of set
+    // if a ∈ A && a ∈ B => A ⋂ B
+    fn intersect(left: &IntervalSet, right: &IntervalSet) -> bool {
+        let (mut i, mut j) = (0, 0);
+        while i < left.ranges.len() && j < right.ranges.len() {
+            if RangeInclusive::intersect(&left.ranges[i], &right.ranges[j]) {
+                return true;
+            }
+            if left.ranges[i].end() < right.ranges[j].end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
         }
         false
     }
@@ -134,23 +878,26 @@ impl Intersection for RangeInclusive<i64> {
 }
 
 impl Compared for DataType {
-    fn compare_with_override(&self, other: Self) -> Option<Ordering> {
+    fn compare_with_override(&self, _other: Self) -> Option<Ordering> {
         unimplemented!()
     }
 }
 
 impl Compared for &DataType {
     fn compare_with_override(&self, other: Self) -> Option<Ordering> {
-        self.compare_with(&other)
+        self.compare_with(other)
     }
 }
 
+#[cfg(test)]
 mod test {
+    use crate::memory::aggregate::AggregateFunction;
     use crate::memory::compared::Compared;
     use crate::memory::intersection::Intersection;
-    use crate::memory::memory_machine::{Indexes, MemoryMachine};
+    use crate::memory::memory_machine::{IntervalSet, MemoryMachine};
+    use crate::memory::operator::{BetweenOperator, Operator};
+    use crate::error::query_error::QueryError;
     use qdb_ast::ast::types::DataType;
-    use std::cmp::Ordering;
     use std::ops::RangeInclusive;
 
     #[test]
@@ -167,15 +914,15 @@ mod test {
         memory_machine.insert(DataType::Null);
 
         debug_assert_eq!(
-            vec![0..=2, 5..=5],
+            IntervalSet::from(vec![0..=2, 5..=5]),
             memory_machine.get(&DataType::Null).unwrap()
         );
         debug_assert_eq!(
-            vec![3..=3],
+            IntervalSet::from(vec![3..=3]),
             memory_machine.get(&DataType::Real(32.0)).unwrap()
         );
         debug_assert_eq!(
-            vec![4..=4],
+            IntervalSet::from(vec![4..=4]),
             memory_machine.get(&DataType::Real(64.0)).unwrap()
         );
 
@@ -198,9 +945,9 @@ mod test {
         memory_machine.insert(DataType::Null);
 
         let result_a =
-            memory_machine.get_values_by_range_inclusive(&vec![RangeInclusive::new(0, 5)]);
+            memory_machine.get_values_by_range_inclusive(&IntervalSet::from_range(0..=5));
         let result_b =
-            memory_machine.get_values_by_range_inclusive(&vec![RangeInclusive::new(5, 8)]);
+            memory_machine.get_values_by_range_inclusive(&IntervalSet::from_range(5..=8));
         println!("{:?}", result_a);
         println!("{:?}", result_b);
     }
@@ -221,6 +968,142 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_memory_machine_insert_at() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert_at(DataType::Int(1), 2);
+        memory_machine.extend_to(4);
+        memory_machine.insert_at(DataType::Int(2), 7);
+        memory_machine.insert_at(DataType::Int(1), 8);
+
+        debug_assert_eq!(
+            IntervalSet::from(vec![2..=6, 8..=8]),
+            memory_machine.get(&DataType::Int(1)).unwrap()
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![7..=7]),
+            memory_machine.get(&DataType::Int(2)).unwrap()
+        );
+        debug_assert_eq!(
+            &DataType::Int(1),
+            memory_machine.get_last_value().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_memory_machine_get_value_at() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert_at(DataType::Int(1), 0);
+        memory_machine.insert_at(DataType::Int(2), 3);
+        memory_machine.insert_at(DataType::Int(1), 4);
+        memory_machine.extend_to(6);
+        memory_machine.delete(&IntervalSet::from_range(5..=5));
+
+        debug_assert_eq!(Some(&DataType::Int(1)), memory_machine.get_value_at(2));
+        debug_assert_eq!(Some(&DataType::Int(2)), memory_machine.get_value_at(3));
+        debug_assert_eq!(Some(&DataType::Int(1)), memory_machine.get_value_at(4));
+        debug_assert_eq!(None, memory_machine.get_value_at(5));
+        debug_assert_eq!(Some(&DataType::Int(1)), memory_machine.get_value_at(6));
+        debug_assert_eq!(None, memory_machine.get_value_at(7));
+
+        debug_assert_eq!(
+            vec![
+                (1..=2, DataType::Int(1)),
+                (3..=3, DataType::Int(2)),
+                (4..=4, DataType::Int(1)),
+                (6..=6, DataType::Int(1)),
+            ],
+            memory_machine.get_history(&IntervalSet::from_range(1..=6))
+        );
+    }
+
+    #[test]
+    fn test_memory_machine_delete() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Int(2));
+
+        memory_machine.delete(&IntervalSet::from_range(1..=1));
+
+        debug_assert!(memory_machine.is_deleted(1));
+        debug_assert_eq!(
+            vec![IntervalSet::from(vec![0..=0, 2..=2])],
+            memory_machine
+                .get_by_compare_with(&DataType::Int(1), |this, other| {
+                    DataType::comparing(this, other, <DataType as Compared>::eq)
+                })
+                .unwrap()
+        );
+        // history is kept
+        debug_assert_eq!(
+            IntervalSet::from(vec![0..=2]),
+            memory_machine.get(&DataType::Int(1)).unwrap()
+        );
+
+        memory_machine.delete(&IntervalSet::from_range(3..=3));
+        debug_assert_eq!(None, memory_machine.get_last_value());
+    }
+
+    #[test]
+    fn test_memory_machine_compact() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Int(2));
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Int(3));
+        memory_machine.insert(DataType::Int(3));
+
+        // tombstones are applied
+        memory_machine.delete(&IntervalSet::from_range(1..=1));
+        memory_machine.compact();
+        debug_assert!(!memory_machine.is_deleted(1));
+        debug_assert_eq!(None, memory_machine.get(&DataType::Int(2)));
+        debug_assert_eq!(None, memory_machine.get_value_at(1));
+        debug_assert_eq!(2, memory_machine.get_distinct_count());
+
+        memory_machine.forget(&IntervalSet::from_range(0..=0));
+        debug_assert_eq!(IntervalSet::from(vec![2..=2]), memory_machine.get(&DataType::Int(1)).unwrap());
+        debug_assert_eq!(None, memory_machine.get_value_at(0));
+        debug_assert_eq!(2, memory_machine.get_ranges_count());
+
+        memory_machine.retain_last_values(1);
+        debug_assert_eq!(None, memory_machine.get(&DataType::Int(1)));
+        debug_assert_eq!(IntervalSet::from(vec![3..=4]), memory_machine.get(&DataType::Int(3)).unwrap());
+        debug_assert_eq!(Some(&DataType::Int(3)), memory_machine.get_last_value());
+
+        memory_machine.forget(&IntervalSet::from_range(0..=4));
+        debug_assert_eq!(0, memory_machine.get_ranges_count());
+        debug_assert_eq!(None, memory_machine.get_last_value());
+    }
+
+    #[test]
+    fn test_memory_machine_forget_before() {
+        let mut memory_machine = MemoryMachine::init();
+        for value in &[1, 1, 2, 1, 3, 3] {
+            memory_machine.insert(DataType::Int(*value));
+        }
+
+        // range which goes on after logic time is cut at it
+        memory_machine.forget_before(1);
+        assert_eq!(None, memory_machine.get_value_at(0));
+        assert_eq!(Some(&DataType::Int(1)), memory_machine.get_value_at(1));
+        assert_eq!(IntervalSet::from(vec![1..=1, 3..=3]), memory_machine.get(&DataType::Int(1)).unwrap());
+
+        memory_machine.forget_before(4);
+        assert_eq!(None, memory_machine.get(&DataType::Int(1)));
+        assert_eq!(None, memory_machine.get(&DataType::Int(2)));
+        assert_eq!(Some(&DataType::Int(3)), memory_machine.get_value_at(4));
+        assert_eq!(Some(&DataType::Int(3)), memory_machine.get_last_value());
+        assert_eq!(1, memory_machine.get_distinct_count());
+        assert_eq!(1, memory_machine.get_ranges_count());
+    }
+
     #[test]
     fn test_memory_machine_get_compare_with() {
         let mut memory_machine = MemoryMachine::init();
@@ -234,7 +1117,211 @@ mod test {
             DataType::comparing(this, other, <DataType as Compared>::eq)
         });
 
-        //println!("{:?}",result)
+        println!("{:?}", result);
+
+        let result = memory_machine.get_by_compare_with(&DataType::Text("text".to_string()), |this, other| {
+            DataType::comparing(this, other, <DataType as Compared>::eq)
+        });
+        debug_assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_memory_machine_get_by_ordering() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert(DataType::Null);
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Real(2.5));
+        memory_machine.insert(DataType::Int(3));
+        memory_machine.insert(DataType::Real(3.0));
+        memory_machine.insert(DataType::Int(5));
+
+        let union = |result: Result<Vec<IntervalSet>, QueryError>| {
+            result
+                .unwrap()
+                .iter()
+                .fold(IntervalSet::new(), |union, indexes| union.union(indexes))
+        };
+
+        debug_assert_eq!(
+            IntervalSet::from(vec![3..=4]),
+            union(memory_machine.get_by_ordering(&DataType::Int(3), <DataType as Compared>::eq))
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![3..=5]),
+            union(memory_machine.get_by_ordering(&DataType::Real(2.5), <DataType as Compared>::gr))
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![2..=5]),
+            union(memory_machine.get_by_ordering(&DataType::Real(2.5), <DataType as Compared>::eq_or_gr))
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![1..=2]),
+            union(memory_machine.get_by_ordering(&DataType::Int(3), <DataType as Compared>::le))
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![1..=4]),
+            union(memory_machine.get_by_ordering(&DataType::Real(3.0), <DataType as Compared>::eq_or_le))
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![1..=2, 5..=5]),
+            union(memory_machine.get_by_ordering(&DataType::Int(3), <DataType as Compared>::neq))
+        );
+        debug_assert_eq!(
+            IntervalSet::new(),
+            union(memory_machine.get_by_ordering(&DataType::Int(i64::MAX), <DataType as Compared>::gr))
+        );
+        debug_assert!(matches!(
+            memory_machine.get_by_ordering(&DataType::Text("text".to_string()), <DataType as Compared>::gr),
+            Err(QueryError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_memory_machine_get_by_ordering_interleaved() {
+        let mut memory_machine = MemoryMachine::init();
+
+        memory_machine.insert(DataType::Int(1));
+        memory_machine.insert(DataType::Real(2.5));
+        memory_machine.insert(DataType::Int(3));
+        memory_machine.insert(DataType::Real(0.5));
+
+        let union = |vec: Vec<IntervalSet>| vec.into_iter().flatten().collect::<IntervalSet>();
+        debug_assert_eq!(
+            IntervalSet::from(vec![1..=2]),
+            union(memory_machine.get_by_ordering(&DataType::Int(2), <DataType as Compared>::eq_or_gr).unwrap())
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![0..=2]),
+            union(memory_machine.get_by_ordering(&DataType::Real(1.0), <DataType as Compared>::eq_or_gr).unwrap())
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![0..=1, 3..=3]),
+            union(memory_machine.get_by_ordering(&DataType::Real(2.5), <DataType as Compared>::eq_or_le).unwrap())
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![0..=0]),
+            union(
+                BetweenOperator
+                    .get_indexes(&memory_machine, &[DataType::Real(0.75), DataType::Int(2)])
+                    .unwrap()
+            )
+        );
+        debug_assert_eq!(
+            IntervalSet::from(vec![0..=2]),
+            union(
+                BetweenOperator
+                    .get_indexes(&memory_machine, &[DataType::Int(1), DataType::Real(3.0)])
+                    .unwrap()
+            )
+        );
+
+        let indexes = IntervalSet::from_range(0..=3);
+        debug_assert_eq!(
+            DataType::Real(0.5),
+            memory_machine.aggregate(AggregateFunction::Min, &indexes).unwrap()
+        );
+        debug_assert_eq!(
+            DataType::Int(3),
+            memory_machine.aggregate(AggregateFunction::Max, &indexes).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_memory_machine_get_by_ordering_with() {
+        let mut a = MemoryMachine::init();
+        a.insert_at(DataType::Int(1), 0);
+        a.insert_at(DataType::Int(5), 2);
+        a.extend_to(5);
+        let mut b = MemoryMachine::init();
+        b.insert_at(DataType::Real(3.0), 1);
+        b.insert_at(DataType::Int(7), 4);
+        b.extend_to(5);
+        let indexes = IntervalSet::from_range(0..=5);
+
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![2..=3])),
+            a.get_by_ordering_with(&b, &indexes, <DataType as Compared>::gr)
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![1..=1, 4..=5])),
+            a.get_by_ordering_with(&b, &indexes, <DataType as Compared>::le)
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![4..=4])),
+            a.get_by_ordering_with(&b, &IntervalSet::from_range(4..=4), <DataType as Compared>::neq)
+        );
+
+        let mut c = MemoryMachine::init();
+        c.insert_at(DataType::Text("text".to_string()), 0);
+        c.extend_to(5);
+        debug_assert!(matches!(
+            a.get_by_ordering_with(&c, &indexes, <DataType as Compared>::eq),
+            Err(QueryError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_memory_machine_aggregate() {
+        let mut memory_machine = MemoryMachine::init();
+        memory_machine.insert_at(DataType::Int(5), 0);
+        memory_machine.insert_at(DataType::Int(2), 3);
+        memory_machine.insert_at(DataType::Null, 4);
+        memory_machine.insert_at(DataType::Int(9), 5);
+        memory_machine.delete(&IntervalSet::from_range(5..=5));
+
+        // 5 at 0..=2, 2 at 3, null at 4, 9 is deleted
+        let indexes = IntervalSet::from_range(0..=5);
+        let aggregate = |function| memory_machine.aggregate(function, &indexes).unwrap();
+        debug_assert_eq!(DataType::Int(4), aggregate(AggregateFunction::Count));
+        debug_assert_eq!(DataType::Int(2), aggregate(AggregateFunction::CountDistinct));
+        debug_assert_eq!(DataType::Int(17), aggregate(AggregateFunction::Sum));
+        debug_assert_eq!(DataType::Int(2), aggregate(AggregateFunction::Min));
+        debug_assert_eq!(DataType::Int(5), aggregate(AggregateFunction::Max));
+        debug_assert_eq!(DataType::Real(4.25), aggregate(AggregateFunction::Avg));
+
+        let empty = IntervalSet::from_range(4..=5);
+        debug_assert_eq!(
+            Ok(DataType::Null),
+            memory_machine.aggregate(AggregateFunction::Max, &empty)
+        );
+        debug_assert_eq!(
+            Ok(DataType::Int(0)),
+            memory_machine.aggregate(AggregateFunction::Count, &empty)
+        );
+
+        memory_machine.insert_at(DataType::Text("a".to_string()), 6);
+        debug_assert!(matches!(
+            memory_machine.aggregate(AggregateFunction::Sum, &IntervalSet::from_range(0..=6)),
+            Err(QueryError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let a = IntervalSet::from(vec![5..=6, 0..=2, 3..=3]);
+        let b = IntervalSet::from(vec![2..=5, 10..=12]);
+
+        debug_assert_eq!(vec![&(0..=3), &(5..=6)], a.iter().collect::<Vec<_>>());
+        debug_assert_eq!(IntervalSet::from(vec![0..=6, 10..=12]), a.union(&b));
+        debug_assert_eq!(IntervalSet::from(vec![2..=3, 5..=5]), a.intersection(&b));
+        debug_assert_eq!(IntervalSet::from(vec![0..=1, 6..=6]), a.difference(&b));
+        debug_assert_eq!(IntervalSet::from(vec![4..=4, 7..=8]), a.complement(&(0..=8)));
+        debug_assert_eq!(IntervalSet::new(), IntervalSet::from_range(0..=8).complement(&(0..=8)));
+
+        debug_assert!(a.contains(3));
+        debug_assert!(!a.contains(4));
+        debug_assert!(a.contains_set(&IntervalSet::from(vec![1..=2, 5..=5])));
+        debug_assert!(!a.contains_set(&b));
+        debug_assert_eq!(6, a.indexes_count());
+        debug_assert_eq!(2, a.ranges_count());
+
+        let mut c = IntervalSet::new();
+        c.insert(10..=12);
+        c.insert(13..=13);
+        c.insert(0..=1);
+        c.insert(11..=20);
+        debug_assert_eq!(IntervalSet::from(vec![0..=1, 10..=20]), c);
     }
 
     #[test]
@@ -242,49 +1329,60 @@ mod test {
         let a_range = RangeInclusive::new(1, 5);
         let b_range = RangeInclusive::new(3, 4);
         let result = RangeInclusive::intersect(&a_range, &b_range);
-        debug_assert_eq!(true, result);
+        debug_assert!(result);
 
         let a_range = RangeInclusive::new(1, 5);
         let b_range = RangeInclusive::new(0, 2);
         let result = RangeInclusive::intersect(&a_range, &b_range);
-        debug_assert_eq!(true, result);
+        debug_assert!(result);
 
         let a_range = RangeInclusive::new(6, 9);
         let b_range = RangeInclusive::new(1, 2);
         let result = RangeInclusive::intersect(&a_range, &b_range);
-        debug_assert_eq!(false, result);
+        debug_assert!(!result);
 
         let a_range = RangeInclusive::new(6, 9);
         let b_range = RangeInclusive::new(10, 12);
         let result = RangeInclusive::intersect(&a_range, &b_range);
-        debug_assert_eq!(false, result);
+        debug_assert!(!result);
     }
 
     #[test]
-    fn test_vec_range_intersection() {
-        let a_range: Indexes = vec![RangeInclusive::new(0, 2), RangeInclusive::new(4, 6)];
-        let b_range: Indexes = vec![RangeInclusive::new(0, 1), RangeInclusive::new(2, 3)];
-        let result = Vec::intersect(&a_range, &b_range);
-        debug_assert_eq!(true, result);
-
-        let a_range: Indexes = vec![RangeInclusive::new(2, 4), RangeInclusive::new(5, 8)];
-        let b_range: Indexes = vec![RangeInclusive::new(3, 5), RangeInclusive::new(8, 10)];
-        let result = Vec::intersect(&a_range, &b_range);
-        debug_assert_eq!(true, result);
-
-        let a_range: Indexes = vec![RangeInclusive::new(0, 1)];
-        let b_range: Indexes = vec![RangeInclusive::new(0, 0)];
-        let result = Vec::intersect(&a_range, &b_range);
-        debug_assert_eq!(true, result);
-
-        let a_range: Indexes = vec![RangeInclusive::new(32, 55), RangeInclusive::new(58, 93)];
-        let b_range: Indexes = vec![RangeInclusive::new(0, 2), RangeInclusive::new(8, 10)];
-        let result = Vec::intersect(&a_range, &b_range);
-        debug_assert_eq!(false, result);
-
-        let a_range: Indexes = vec![RangeInclusive::new(32, 55), RangeInclusive::new(58, 93)];
-        let b_range: Indexes = vec![RangeInclusive::new(93, 108), RangeInclusive::new(110, 120)];
-        let result = Vec::intersect(&a_range, &b_range);
-        debug_assert_eq!(false, result);
+    fn test_interval_set_intersection() {
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(0, 2), RangeInclusive::new(4, 6)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(0, 1), RangeInclusive::new(2, 3)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(result);
+
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(2, 4), RangeInclusive::new(5, 8)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(3, 5), RangeInclusive::new(8, 10)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(result);
+
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(0, 1)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(0, 0)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(result);
+
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(32, 55), RangeInclusive::new(58, 93)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(0, 2), RangeInclusive::new(8, 10)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(!result);
+
+        // 93 is in both sets
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(32, 55), RangeInclusive::new(58, 93)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(93, 108), RangeInclusive::new(110, 120)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(result);
+
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(0, 2), RangeInclusive::new(10, 12)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(5, 6)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(!result);
+
+        let a_range = IntervalSet::from(vec![RangeInclusive::new(0, 2), RangeInclusive::new(10, 12)]);
+        let b_range = IntervalSet::from(vec![RangeInclusive::new(5, 11)]);
+        let result = IntervalSet::intersect(&a_range, &b_range);
+        debug_assert!(result);
     }
 }
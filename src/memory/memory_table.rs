@@ -1,230 +1,1011 @@
-use crate::memory::compared::Compared;
-use crate::memory::memory_machine::{Indexes, MemoryMachine};
+use crate::memory::aggregate::Aggregate;
+use crate::memory::memory_machine::{IntervalSet, MemoryMachine};
+use crate::memory::operator::{get_ordering_predicate, OperatorRegistry};
+use crate::memory::predicate::Predicate;
 use crate::memory::print_of_state::PrintOfState;
+use crate::memory::result_set::{ResultSet, Row};
+use crate::memory::retention_policy::RetentionPolicy;
+use crate::memory::schema::{Schema, ValueType};
+use crate::memory::subscription::Subscription;
+use crate::error::query_error::QueryError;
 use qdb_ast::ast::types::{BinaryExpr, DataType, DataVar};
-use std::borrow::BorrowMut;
-use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
+use std::mem::size_of;
+use std::ops::RangeInclusive;
 
 // variable storage
+// logic_time - row clock shared by all variables of table,
+// one tick is assigned once per write of row
+// schema - types of variables, established by first row
+// retention - part of history kept, it's applied on every write of row,
+// so reads never see history out of it and it's pruned deterministically on log replay
+// subscriptions - listeners of written rows, they aren't saved to snapshot
 #[derive(Debug)]
 pub struct MemoryTable {
     mem: HashMap<String, MemoryMachine>,
+    schema: Schema,
+    retention: Option<RetentionPolicy>,
+    subscriptions: Vec<Subscription>,
+    logic_time: i64,
 }
 
 impl MemoryTable {
     // hidden function
-    fn get_symbol(data_type: &DataType) -> Option<&String> {
-        return match data_type {
+    // Symbol qualified by channel (my_node.c) is variable of this table,
+    // it's bound to channel by query resolver
+    fn get_var_name(symbol: &str) -> &str {
+        symbol.split_once('.').map_or(symbol, |(_, name)| name)
+    }
+
+    fn get_symbol(data_type: &DataType) -> Option<&str> {
+        match data_type {
             DataType::Symbol(val) => Some(val),
             _ => None,
-        };
+        }
     }
 
-    fn get_operator(operator: &str) -> fn(&Ordering) -> bool {
-        return match operator {
-            "==" => <DataType as Compared>::eq,
-            "!=" => <DataType as Compared>::neq,
-            ">=" => <DataType as Compared>::eq_or_gr,
-            ">" => <DataType as Compared>::gr,
-            "<=" => <DataType as Compared>::eq_or_le,
-            "<" => <DataType as Compared>::le,
-            _ => panic!("Error in operator detection"),
-        };
+    // Every operand of predicate which is unknown variable is UnknownVariable error
+    // with symbol as it's written in query
+    fn get_mem_machine(&self, symbol: &str) -> Result<&MemoryMachine, QueryError> {
+        self.mem
+            .get(Self::get_var_name(symbol))
+            .ok_or_else(|| QueryError::UnknownVariable(symbol.to_string()))
+    }
+
+    // To get indexes of values of variable which satisfy operator from registry
+    fn get_indexes_by_operator(
+        &self,
+        name: &str,
+        operator: &str,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        let mem_machine = self.get_mem_machine(name)?;
+        OperatorRegistry::global().get(operator)?.get_indexes(mem_machine, operands)
     }
 
     fn resolve_symbol_operator_get_indexes(
         &self,
-        maybe_left_symbol: Option<&String>,
+        maybe_left_symbol: Option<&str>,
         right: &DataType,
         operator: &str,
-    ) -> Option<Vec<&Indexes>> {
-        if maybe_left_symbol.is_some() {
-            let value = maybe_left_symbol.unwrap();
+    ) -> Result<Option<Vec<IntervalSet>>, QueryError> {
+        if let Some(value) = maybe_left_symbol {
+            let indexes = self.get_indexes_by_operator(value, operator, std::slice::from_ref(right))?;
+            return Ok(Some(indexes));
+        }
+        Ok(None)
+    }
+
+    // To get indexes where value of left variable relates to value of right one
+    // by ordering operator: a > b
+    fn get_indexes_by_variables(&self, left: &str, operator: &str, right: &str) -> Result<IntervalSet, QueryError> {
+        let predicate =
+            get_ordering_predicate(operator).ok_or_else(|| QueryError::UnsupportedOperator(operator.to_string()))?;
+        let indexes = IntervalSet::from_range(0..=self.logic_time - 1);
+        self.get_mem_machine(left)?
+            .get_by_ordering_with(self.get_mem_machine(right)?, &indexes, predicate)
+    }
+
+    // public function
+    pub fn init() -> Self {
+        MemoryTable {
+            mem: HashMap::new(),
+            schema: Schema::new(),
+            retention: None,
+            subscriptions: Vec::new(),
+            logic_time: 0,
+        }
+    }
+    // To insert one variable as separate row
+    pub fn insert(&mut self, name_var: &str, value: DataType) -> Result<(), QueryError> {
+        self.insert_row(vec![(name_var, value)])
+    }
+    // To get schema with types annotated by query (c:int = 1):
+    // variable without type gets annotated one, type of declared variable must be it
+    // or its widening (int annotation of real variable)
+    fn get_declared_schema(&self, types: &[(String, ValueType)]) -> Result<Schema, QueryError> {
+        let mut schema = self.schema.clone();
+        for (name, value_type) in types {
+            match schema.get_type(name) {
+                Some(declared) if !value_type.is_widened_to(declared) => {
+                    return Err(QueryError::TypeMismatch(format!(
+                        "variable {} is declared as {}, found {}",
+                        name,
+                        declared.get_name(),
+                        value_type.get_name()
+                    )));
+                }
+                Some(_) => {}
+                None => schema.declare(name, Some(*value_type))?,
+            }
+        }
+        Ok(schema)
+    }
+
+    fn check_row_by<'a>(
+        schema: &Schema,
+        row: Vec<(&'a str, DataType)>,
+    ) -> Result<Vec<(&'a str, DataType)>, QueryError> {
+        for (position, (name_var, _)) in row.iter().enumerate() {
+            if row[..position].iter().any(|(name, _)| name == name_var) {
+                return Err(QueryError::ParseError(format!("variable is written twice in row: {}", name_var)));
+            }
+        }
+        row.into_iter()
+            .map(|(name_var, value)| {
+                if !schema.contains(name_var) {
+                    return Ok((name_var, value));
+                }
+                schema.check(name_var, value).map(|value| (name_var, value))
+            })
+            .collect()
+    }
+
+    // To check row by schema and types annotated by query, values of declared variables
+    // are converted to their types. Returns TypeMismatch for value or annotation of other type
+    // and ParseError for variable written twice.
+    pub fn check_row<'a>(
+        &self,
+        row: Vec<(&'a str, DataType)>,
+        types: &[(String, ValueType)],
+    ) -> Result<Vec<(&'a str, DataType)>, QueryError> {
+        Self::check_row_by(&self.get_declared_schema(types)?, row)
+    }
+
+    // To insert row: all variables of row get same logic time,
+    // variables out of row keep their last values at this logic time.
+    // First value of variable declares its type in schema, next values are checked by it,
+    // row isn't written if check fails.
+    pub fn insert_row(&mut self, row: Vec<(&str, DataType)>) -> Result<(), QueryError> {
+        self.insert_declared_row(row, &[])
+    }
+
+    // Same as insert_row, but variables are declared by types annotated by query before
+    // their first values, annotations are kept only if row is written
+    pub fn insert_declared_row(
+        &mut self,
+        row: Vec<(&str, DataType)>,
+        types: &[(String, ValueType)],
+    ) -> Result<(), QueryError> {
+        let schema = self.get_declared_schema(types)?;
+        let row = Self::check_row_by(&schema, row)?;
+        self.schema = schema;
 
-            let maybe_mem_machine = self.mem.get(value);
+        let logic_time = self.logic_time;
+        for (name_var, value) in row {
+            if self.schema.get_type(name_var).is_none() {
+                self.schema.declare(name_var, ValueType::of(&value))?;
+            }
+            if let Some(mem_machine) = self.mem.get_mut(name_var) {
+                mem_machine.insert_at(value, logic_time);
+            } else {
+                let mut mem_machine = MemoryMachine::init();
+                mem_machine.insert_at(value, logic_time);
+                self.mem.insert(name_var.to_string(), mem_machine);
+            }
+        }
+        for (_, mem_machine) in self.mem.iter_mut() {
+            mem_machine.extend_to(logic_time);
+        }
+        self.logic_time += 1;
+        self.apply_retention();
+        if !self.subscriptions.is_empty() {
+            let row_table = self.get_row_table(logic_time);
+            self.subscriptions
+                .retain(|subscription| subscription.notify(&row_table, logic_time));
+        }
+        Ok(())
+    }
+
+    // To notify subscription about every written row where its condition is true
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        self.subscriptions.push(subscription);
+    }
+
+    // Returns false if there is no subscription with id
+    pub fn unsubscribe(&mut self, id: u64) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|subscription| subscription.get_id() != id);
+        self.subscriptions.len() < count
+    }
 
-            if maybe_mem_machine.is_some() {
-                let mem_machine = maybe_mem_machine.unwrap();
-                let predicate = Self::get_operator(operator);
+    pub fn get_subscriptions_count(&self) -> usize {
+        self.subscriptions.len()
+    }
 
-                let indexes = mem_machine.get_by_compare_with(right, |this, other| {
-                    DataType::comparing(this, other, predicate)
-                });
+    // To set part of history kept by compaction, None keeps full history
+    pub fn set_retention(&mut self, retention: Option<RetentionPolicy>) {
+        self.retention = retention;
+    }
+
+    pub fn get_retention(&self) -> Option<RetentionPolicy> {
+        self.retention
+    }
 
-                return Some(indexes);
+    // To drop history which goes out of retention policy by last write,
+    // only its part out of policy is visited
+    fn apply_retention(&mut self) {
+        let retention = match self.retention {
+            Some(retention) => retention,
+            None => return,
+        };
+        for mem_machine in self.mem.values_mut() {
+            match retention {
+                RetentionPolicy::Ticks(count) => mem_machine.forget_before(self.logic_time - count),
+                RetentionPolicy::Watermark(logic_time) => mem_machine.forget_before(logic_time),
+                RetentionPolicy::Values(count) => mem_machine.retain_last_values(count),
             }
         }
-        None
     }
 
-    // public function
-    pub fn init() -> Self {
-        MemoryTable {
-            mem: HashMap::new(),
+    // To drop history out of retention policy and tombstoned states,
+    // values without history are removed from variables
+    pub fn compact(&mut self) {
+        let forgotten = match self.retention {
+            Some(RetentionPolicy::Ticks(count)) => IntervalSet::from_range(0..=self.logic_time - 1 - count),
+            Some(RetentionPolicy::Watermark(logic_time)) => IntervalSet::from_range(0..=logic_time.saturating_sub(1)),
+            _ => IntervalSet::new(),
+        };
+        for mem_machine in self.mem.values_mut() {
+            match self.retention {
+                Some(RetentionPolicy::Values(count)) => {
+                    mem_machine.retain_last_values(count);
+                    mem_machine.compact();
+                }
+                _ if !forgotten.is_empty() => mem_machine.forget(&forgotten),
+                _ => mem_machine.compact(),
+            }
         }
     }
-    pub fn insert(&mut self, name_var: &str, value: DataType) {
-        let maybe_mem_machine = self.mem.get_mut(name_var);
-        if maybe_mem_machine.is_some() {
-            let mut mem_machine = maybe_mem_machine.unwrap();
-            mem_machine.insert(value);
-        } else {
-            let mut mem_machine = MemoryMachine::init();
-            mem_machine.insert(value);
-            self.mem.insert(name_var.to_string(), mem_machine);
+
+    // To add variables or widen their types (int -> real).
+    // Schema is changed only if all declarations are valid.
+    pub fn alter(&mut self, declarations: &[(String, ValueType)]) -> Result<(), QueryError> {
+        let mut schema = self.schema.clone();
+        for (name, value_type) in declarations {
+            schema.declare(name, Some(*value_type))?;
         }
+        for (name, _) in declarations {
+            if !self.mem.contains_key(name) {
+                self.mem.insert(name.to_string(), MemoryMachine::init());
+            }
+        }
+        self.schema = schema;
+        Ok(())
+    }
+
+    pub fn get_schema(&self) -> &Schema {
+        &self.schema
+    }
+    // To get logic time of next row
+    pub fn get_logic_time(&self) -> i64 {
+        self.logic_time
     }
     pub fn find(&self, var: &DataVar) -> Option<Vec<DataType>> {
         let (name, value) = var.get();
-        let maybe_mem_machine = self.mem.get(name.as_str());
-
-        if maybe_mem_machine.is_some() {
-            let mem_machine = maybe_mem_machine.unwrap();
-            let maybe_range = mem_machine.get(value);
-
-            if maybe_range.is_some() {
-                let range = maybe_range.unwrap();
-                for (_, mem) in self.mem.iter() {
-                    let data_types = mem.get_values_by_range_inclusive(&range);
-                    if data_types.len() > 0 {
-                        return Some(data_types);
-                    }
-                    return None;
+        let range = self.mem.get(name.as_str())?.get(value)?;
+        // values at indexes of value of first variable in alphabetical order which has them
+        self.get_var_names()
+            .into_iter()
+            .map(|name| self.mem[name].get_values_by_range_inclusive(&range))
+            .find(|data_types| !data_types.is_empty())
+    }
+    pub fn find_by_predicate(
+        &self,
+        binary_expr: &BinaryExpr,
+    ) -> Result<Option<Vec<PrintOfState>>, QueryError> {
+        let maybe_indexes = self.find_indexes_by_predicate(binary_expr)?;
+        let indexes = match maybe_indexes {
+            Some(indexes) if !indexes.is_empty() => indexes,
+            _ => return Ok(None),
+        };
+
+        let mut vec: Vec<PrintOfState> = Vec::new();
+        for (key, mem) in self.mem.iter() {
+            let data_types = mem.get_values_by_range_inclusive(&indexes);
+            vec.push(PrintOfState::new(key, data_types));
+        }
+        Ok(Some(vec))
+    }
+
+    pub fn find_by_predicate_intense(
+        &self,
+        binary_expr: &BinaryExpr,
+    ) -> Result<Option<Vec<PrintOfState>>, QueryError> {
+        let maybe_indexes = self.find_indexes_by_predicate(binary_expr)?;
+        let indexes = match maybe_indexes {
+            Some(indexes) if !indexes.is_empty() => indexes,
+            _ => return Ok(None),
+        };
+
+        let mut vec: Vec<PrintOfState> = Vec::new();
+        for (key, mem) in self.mem.iter() {
+            let mut data_types = mem.get_values_by_range_inclusive(&indexes);
+            if data_types.is_empty() {
+                if let Some(last_value) = mem.get_last_value() {
+                    data_types = vec![last_value.clone()];
                 }
             }
+            vec.push(PrintOfState::new(key, data_types));
         }
-        None
+        Ok(Some(vec))
     }
-    pub fn find_by_predicate(&self, binary_expr: &BinaryExpr) -> Option<Vec<PrintOfState>> {
+
+    // To get all indexes (states) where predicate is true
+    pub fn find_indexes_by_predicate(
+        &self,
+        binary_expr: &BinaryExpr,
+    ) -> Result<Option<IntervalSet>, QueryError> {
         let (left, right, operator) = binary_expr.get();
-        // todo: refactor it
 
-        {
-            let maybe_l_value = Self::get_symbol(left);
-            let maybe_r_value = Self::get_symbol(right);
+        let maybe_l_value = Self::get_symbol(left);
+        let maybe_r_value = Self::get_symbol(right);
 
-            if maybe_l_value.is_some() && maybe_r_value.is_some() {
-                return None;
-            }
+        if let (Some(left), Some(right)) = (maybe_l_value, maybe_r_value) {
+            return self.get_indexes_by_variables(left, operator, right).map(Some);
+        }
+
+        let mut maybe_result =
+            self.resolve_symbol_operator_get_indexes(maybe_l_value, right, operator)?;
+        if maybe_result.is_none() {
+            maybe_result =
+                self.resolve_symbol_operator_get_indexes(maybe_r_value, left, operator)?;
+        }
 
-            let result_from_l =
-                self.resolve_symbol_operator_get_indexes(maybe_l_value, right, operator);
-            let result_from_r =
-                self.resolve_symbol_operator_get_indexes(maybe_r_value, left, operator);
-
-            if result_from_l.is_some() {
-                let indexes = *result_from_l.unwrap().get(0).unwrap(); // why .get(0) ?, what wrong ?
-                let mut vec: Vec<PrintOfState> = Vec::new();
-                for (key, mem) in self.mem.iter() {
-                    let data_types = mem.get_values_by_range_inclusive(indexes);
-                    vec.push(PrintOfState::new(key, data_types));
+        Ok(maybe_result.map(|result| result.into_iter().flatten().collect()))
+    }
+
+    // To get set of indexes (logic time intervals) where predicate is true.
+    // Expressions are combined as sets: And - ⋂, Or - ⋃, Not - complement
+    // within logic time of table.
+    pub fn find_indexes_by_condition(
+        &self,
+        predicate: &Predicate,
+    ) -> Result<IntervalSet, QueryError> {
+        match predicate {
+            Predicate::Expr(binary_expr) => {
+                let maybe_indexes = self.find_indexes_by_predicate(binary_expr)?;
+                Ok(maybe_indexes.unwrap_or_default())
+            }
+            Predicate::Operation(operation) => {
+                let vec = self.get_indexes_by_operator(
+                    operation.get_name(),
+                    operation.get_operator(),
+                    operation.get_operands(),
+                )?;
+                Ok(vec.into_iter().flatten().collect())
+            }
+            Predicate::And(predicates) => {
+                // conjunction without expressions is true at any logic time
+                let mut result = IntervalSet::from_range(0..=self.logic_time - 1);
+                for predicate in predicates {
+                    let indexes = self.find_indexes_by_condition(predicate)?;
+                    result = result.intersection(&indexes);
                 }
-                return Some(vec);
+                Ok(result)
             }
-            if result_from_r.is_some() {
-                let indexes = *result_from_r.unwrap().get(0).unwrap(); // why .get(0) ?, what wrong ?
-                let mut vec: Vec<PrintOfState> = Vec::new();
-                for (key, mem) in self.mem.iter() {
-                    let data_types = mem.get_values_by_range_inclusive(indexes);
-                    vec.push(PrintOfState::new(key, data_types));
+            Predicate::Or(predicates) => {
+                let mut result = IntervalSet::new();
+                for predicate in predicates {
+                    let indexes = self.find_indexes_by_condition(predicate)?;
+                    result = result.union(&indexes);
                 }
-                return Some(vec);
+                Ok(result)
             }
-            return None;
+            Predicate::Not(predicate) => {
+                let indexes = self.find_indexes_by_condition(predicate)?;
+                Ok(indexes.complement(&(0..=self.logic_time - 1)))
+            }
+        }
+    }
+
+    // To get values of all variables at indexes where predicate is true.
+    // Variables without values at these indexes are skipped.
+    pub fn find_by_condition(&self, predicate: &Predicate) -> Result<Vec<PrintOfState>, QueryError> {
+        let indexes = self.find_indexes_by_condition(predicate)?;
+        let mut vec: Vec<PrintOfState> = Vec::new();
+        if indexes.is_empty() {
+            return Ok(vec);
         }
 
-        None
+        for name in self.get_var_names() {
+            let data_types = self.mem[name].get_values_by_range_inclusive(&indexes);
+            if !data_types.is_empty() {
+                vec.push(PrintOfState::new(name, data_types));
+            }
+        }
+        Ok(vec)
     }
 
-    pub fn find_by_predicate_intense(&self, binary_expr: &BinaryExpr) -> Option<Vec<PrintOfState>> {
-        let (left, right, operator) = binary_expr.get();
-        {
-            let maybe_l_value = Self::get_symbol(left);
-            let maybe_r_value = Self::get_symbol(right);
+    // To get values of all variables in order of logic time
+    // at indexes inside window where predicate is true.
+    pub fn find_by_condition_in_window(
+        &self,
+        predicate: &Predicate,
+        window: &IntervalSet,
+    ) -> Result<Vec<PrintOfState>, QueryError> {
+        let indexes = self.find_indexes_by_condition(predicate)?.intersection(window);
+        let mut vec: Vec<PrintOfState> = Vec::new();
+        if indexes.is_empty() {
+            return Ok(vec);
+        }
 
-            if maybe_l_value.is_some() && maybe_r_value.is_some() {
-                return None;
+        for name in self.get_var_names() {
+            let data_types: Vec<DataType> = self.mem[name]
+                .get_history(&indexes)
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect();
+            if !data_types.is_empty() {
+                vec.push(PrintOfState::new(name, data_types));
             }
+        }
+        Ok(vec)
+    }
 
-            let result_from_l =
-                self.resolve_symbol_operator_get_indexes(maybe_l_value, right, operator);
-            let result_from_r =
-                self.resolve_symbol_operator_get_indexes(maybe_r_value, left, operator);
-
-            if result_from_l.is_some() {
-                let indexes = *result_from_l.unwrap().get(0).unwrap();
-                let mut vec: Vec<PrintOfState> = Vec::new();
-                for (key, mem) in self.mem.iter() {
-                    let mut data_types = mem.get_values_by_range_inclusive(indexes);
-                    if data_types.is_empty() {
-                        data_types = vec![mem.get_last_value().unwrap().clone()];
-                    }
-                    vec.push(PrintOfState::new(key, data_types));
-                }
+    // To get rows of table at indexes where predicate is true (inside window, if any).
+    // Row is cut at every change of any variable, so each cell is the value
+    // the variable had during logic times of row.
+    pub fn find_rows_by_condition(
+        &self,
+        predicate: &Predicate,
+        window: Option<&IntervalSet>,
+    ) -> Result<ResultSet, QueryError> {
+        let names: Vec<String> = self.get_var_names().into_iter().cloned().collect();
+        self.find_projected_rows_by_condition(predicate, window, &names)
+    }
+
+    // Same as find_rows_by_condition, but only named variables are read
+    // and rows are cut only at changes of them.
+    pub fn find_projected_rows_by_condition(
+        &self,
+        predicate: &Predicate,
+        window: Option<&IntervalSet>,
+        names: &[String],
+    ) -> Result<ResultSet, QueryError> {
+        let mut mem_machines = Vec::with_capacity(names.len());
+        for name in names {
+            let mem_machine = self
+                .mem
+                .get(name)
+                .ok_or_else(|| QueryError::UnknownVariable(name.to_string()))?;
+            mem_machines.push(mem_machine);
+        }
 
-                return Some(vec);
+        let mut indexes = self.find_indexes_by_condition(predicate)?;
+        if let Some(window) = window {
+            indexes = indexes.intersection(window);
+        }
+        let mut result_set = ResultSet::new(names.to_vec());
+        let histories: Vec<Vec<(RangeInclusive<i64>, DataType)>> = mem_machines
+            .iter()
+            .map(|mem_machine| mem_machine.get_history(&indexes))
+            .collect();
+
+        let mut cuts: BTreeSet<i64> = indexes.iter().map(|range| *range.start()).collect();
+        for history in histories.iter() {
+            for (range, _) in history {
+                cuts.insert(*range.start());
+                cuts.insert(range.end() + 1);
             }
+        }
 
-            if result_from_r.is_some() {
-                let indexes = *result_from_r.unwrap().get(0).unwrap();
-                let mut vec: Vec<PrintOfState> = Vec::new();
-                for (key, mem) in self.mem.iter() {
-                    let mut data_types = mem.get_values_by_range_inclusive(indexes);
-                    if data_types.is_empty() {
-                        data_types = vec![mem.get_last_value().unwrap().clone()];
+        // position of current segment in history of every variable
+        let mut cursors = vec![0; histories.len()];
+        for range in indexes.iter() {
+            let starts: Vec<i64> = cuts.range(range.clone()).cloned().collect();
+            for (index, start) in starts.iter().enumerate() {
+                let end = starts.get(index + 1).map_or(*range.end(), |next| next - 1);
+                let mut is_empty = true;
+                let mut values = Vec::with_capacity(histories.len());
+                for (history, cursor) in histories.iter().zip(cursors.iter_mut()) {
+                    while *cursor < history.len() && history[*cursor].0.end() < start {
+                        *cursor += 1;
+                    }
+                    match history.get(*cursor) {
+                        Some((segment, value)) if segment.contains(start) => {
+                            is_empty = false;
+                            values.push(value.clone());
+                        }
+                        _ => values.push(DataType::Null),
                     }
-                    vec.push(PrintOfState::new(key, data_types));
                 }
+                // logic time deleted or without values of all variables
+                if !is_empty {
+                    result_set.push_row(Row::new(*start..=end, values));
+                }
+            }
+        }
+        Ok(result_set)
+    }
+
+    // To aggregate variables at indexes where predicate is true (inside window, if any).
+    // Result has one row over logic times of window or whole table,
+    // one column for each aggregate.
+    pub fn aggregate_by_condition(
+        &self,
+        predicate: &Predicate,
+        window: Option<&IntervalSet>,
+        aggregates: &[Aggregate],
+    ) -> Result<ResultSet, QueryError> {
+        for aggregate in aggregates {
+            if !self.mem.contains_key(aggregate.get_name()) {
+                return Err(QueryError::UnknownVariable(aggregate.get_name().to_string()));
+            }
+        }
+
+        let mut indexes = self.find_indexes_by_condition(predicate)?;
+        let bounds = match window {
+            Some(window) => {
+                indexes = indexes.intersection(window);
+                window.start().unwrap_or(0)..=window.end().unwrap_or(-1)
+            }
+            None => 0..=self.logic_time - 1,
+        };
+
+        let mut values = Vec::with_capacity(aggregates.len());
+        for aggregate in aggregates {
+            let mem_machine = &self.mem[aggregate.get_name()];
+            values.push(mem_machine.aggregate(aggregate.get_function(), &indexes)?);
+        }
+        let mut result_set = ResultSet::new(aggregates.iter().map(Aggregate::get_label).collect());
+        result_set.push_row(Row::new(bounds, values));
+        Ok(result_set)
+    }
+
+    // To get state of table as of logic time
+    pub fn read_at(&self, logic_time: i64) -> Vec<PrintOfState> {
+        let mut vec: Vec<PrintOfState> = Vec::new();
+        for name in self.get_var_names() {
+            if let Some(value) = self.mem[name].get_value_at(logic_time) {
+                vec.push(PrintOfState::new(name, vec![value.clone()]));
+            }
+        }
+        vec
+    }
 
-                return Some(vec);
+    // To get state of table at logic time as table of one row (at logic time 0),
+    // so condition is evaluated at this row only, without scan of history
+    pub fn get_row_table(&self, logic_time: i64) -> MemoryTable {
+        let mut mem: HashMap<String, MemoryMachine> = HashMap::new();
+        for name in self.get_var_names() {
+            if let Some(value) = self.mem[name].get_value_at(logic_time) {
+                let mut mem_machine = MemoryMachine::init();
+                mem_machine.insert(value.clone());
+                mem.insert(name.to_string(), mem_machine);
             }
         }
-        None
+        MemoryTable::restore(1, mem, self.schema.clone(), None)
+    }
+
+    // To get full history of table between logic times (inclusive)
+    pub fn read_between(&self, from: i64, to: i64) -> Vec<PrintOfState> {
+        let window = IntervalSet::from_range(from..=to);
+        self.find_by_condition_in_window(&Predicate::all(Vec::new()), &window)
+            .unwrap_or_default()
+    }
+
+    // To get logic time of last row which isn't tombstoned or in deleted indexes
+    fn find_last_live_logic_time(&self, deleted: &IntervalSet) -> Option<i64> {
+        let deleted = self
+            .mem
+            .values()
+            .fold(deleted.clone(), |deleted, mem_machine| deleted.union(mem_machine.get_deleted()));
+        IntervalSet::from_range(0..=self.logic_time - 1)
+            .difference(&deleted)
+            .end()
+    }
+
+    // To get logic time of current row - last one which isn't tombstoned
+    pub fn get_last_live_logic_time(&self) -> Option<i64> {
+        self.find_last_live_logic_time(&IntervalSet::new())
+    }
+
+    // To get current row as table of one row (at logic time 0), states at deleted indexes
+    // are taken as tombstoned. Every variable of table is kept, so table has no rows
+    // if there is no live row.
+    pub fn get_current_row_table(&self, deleted: &IntervalSet) -> MemoryTable {
+        let logic_time = self.find_last_live_logic_time(deleted);
+        let mut mem: HashMap<String, MemoryMachine> = HashMap::new();
+        for (name, mem_machine) in self.mem.iter() {
+            let mut row_machine = MemoryMachine::init();
+            if let Some(value) = logic_time.and_then(|logic_time| mem_machine.get_value_at(logic_time)) {
+                row_machine.insert(value.clone());
+            }
+            mem.insert(name.to_string(), row_machine);
+        }
+        MemoryTable::restore(logic_time.map_or(0, |_| 1), mem, self.schema.clone(), None)
+    }
+
+    // To append new values of vars if predicate is true at current row,
+    // history isn't matched. Values are written at current logical time as one row.
+    pub fn update_by_predicate(
+        &mut self,
+        predicate: &Predicate,
+        vars: &[DataVar],
+    ) -> Result<bool, QueryError> {
+        for var in vars {
+            let (name, _) = var.get();
+            if !self.is_var_exist(name) {
+                return Err(QueryError::UnknownVariable(name.to_string()));
+            }
+        }
+
+        let indexes = self.find_indexes_by_condition(predicate)?;
+        match self.get_last_live_logic_time() {
+            Some(logic_time) if indexes.contains(logic_time) => {}
+            _ => return Ok(false),
+        }
+
+        let row = vars
+            .iter()
+            .map(|var| {
+                let (name, value) = var.get();
+                (name.as_str(), value.clone())
+            })
+            .collect();
+        self.insert_row(row)?;
+        Ok(true)
+    }
+
+    // To tombstone states where predicate is true.
+    // History is kept, but deleted states are hidden from reads.
+    pub fn delete_by_predicate(&mut self, predicate: &Predicate) -> Result<bool, QueryError> {
+        let indexes = self.find_indexes_by_condition(predicate)?;
+        if indexes.is_empty() {
+            return Ok(false);
+        }
+        self.delete_indexes(&indexes);
+        Ok(true)
+    }
+
+    // To tombstone states of all variables at indexes
+    pub fn delete_indexes(&mut self, indexes: &IntervalSet) {
+        for (_, mem) in self.mem.iter_mut() {
+            mem.delete(indexes);
+        }
+    }
+
+    // To get names of variables in alphabetical order
+    pub fn get_var_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.mem.keys().collect();
+        names.sort();
+        names
     }
 
     pub fn is_var_exist(&self, name: &String) -> bool {
         self.mem.contains_key(name)
     }
+
+    pub fn get_var(&self, name: &str) -> Option<&MemoryMachine> {
+        self.mem.get(name)
+    }
+
+    // To estimate bytes used by all variables of table
+    pub fn get_memory_usage(&self) -> usize {
+        self.mem
+            .iter()
+            .map(|(name, mem_machine)| name.capacity() + mem_machine.get_memory_usage())
+            .sum::<usize>()
+            + size_of::<MemoryTable>()
+    }
+
+    // To restore table from its full state, e.g. read from snapshot.
+    // Subscriptions aren't part of state, restored table has no listeners.
+    pub fn restore(
+        logic_time: i64,
+        mem: HashMap<String, MemoryMachine>,
+        schema: Schema,
+        retention: Option<RetentionPolicy>,
+    ) -> Self {
+        MemoryTable {
+            mem,
+            schema,
+            retention,
+            subscriptions: Vec::new(),
+            logic_time,
+        }
+    }
 }
 
+#[cfg(test)]
 mod test {
     use crate::memory::memory_table::MemoryTable;
+    use crate::memory::memory_machine::IntervalSet;
+    use crate::memory::predicate::Predicate;
     use crate::memory::print_of_state::PrintOfState;
+    use crate::memory::result_set::Row;
+    use crate::memory::retention_policy::RetentionPolicy;
+    use crate::memory::schema::ValueType;
+    use crate::error::query_error::QueryError;
     use qdb_ast::ast::types::{BinaryExpr, DataType, DataVar};
 
     #[test]
     fn test_memory_table_insert() {
         let mut memory_table = MemoryTable::init();
-        memory_table.insert("A", DataType::Text("mytext".to_string()));
-        memory_table.insert("A", DataType::Null);
-        memory_table.insert("B", DataType::Null);
-        memory_table.insert("A", DataType::Real(56.01));
-        memory_table.insert("A", DataType::Int(32));
-        memory_table.insert("B", DataType::Null);
+        memory_table.insert("A", DataType::Text("mytext".to_string())).unwrap();
+        memory_table.insert("A", DataType::Null).unwrap();
+        memory_table.insert("B", DataType::Null).unwrap();
+        memory_table.insert("A", DataType::Text("othertext".to_string())).unwrap();
+        memory_table.insert("B", DataType::Int(32)).unwrap();
 
         println!("{:#?}", memory_table);
+
+        // type of variable is declared by its first value
+        debug_assert_eq!(Some(ValueType::Text), memory_table.get_schema().get_type("A"));
+        debug_assert_eq!(Some(ValueType::Int), memory_table.get_schema().get_type("B"));
+        debug_assert!(matches!(
+            memory_table.insert_row(vec![("B", DataType::Int(1)), ("A", DataType::Real(56.01))]),
+            Err(QueryError::TypeMismatch(_))
+        ));
+        debug_assert_eq!(5, memory_table.get_logic_time());
+
+        // int is widened to real
+        memory_table.alter(&[("B".to_string(), ValueType::Real), ("C".to_string(), ValueType::Int)]).unwrap();
+        memory_table.insert_row(vec![("B", DataType::Int(1)), ("C", DataType::Int(2))]).unwrap();
+        debug_assert_eq!(Some(&DataType::Real(1.0)), memory_table.get_var("B").unwrap().get_last_value());
+        debug_assert!(matches!(
+            memory_table.alter(&[("B".to_string(), ValueType::Int)]),
+            Err(QueryError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_memory_table_insert_row() {
+        let mut memory_table = MemoryTable::init();
+        memory_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        memory_table.insert("a", DataType::Int(3)).unwrap();
+        memory_table.insert("a", DataType::Int(4)).unwrap();
+        memory_table.insert_row(vec![("b", DataType::Int(5))]).unwrap();
+
+        debug_assert_eq!(4, memory_table.get_logic_time());
+
+        // b keeps value 2 while a is written alone
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Int(4),
+            "==".to_string(),
+        );
+        let vec_print_of_state = memory_table.find_by_predicate(&binary_expr).unwrap().unwrap();
+        debug_assert!(vec_print_of_state.contains(&PrintOfState::new(
+            &"b".to_string(),
+            vec![DataType::Int(2), DataType::Int(5)]
+        )));
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Int(3),
+            "==".to_string(),
+        );
+        let vec_print_of_state = memory_table.find_by_predicate(&binary_expr).unwrap().unwrap();
+        debug_assert!(vec_print_of_state.contains(&PrintOfState::new(
+            &"b".to_string(),
+            vec![DataType::Int(2)]
+        )));
+
+        // row with same variable twice isn't written
+        assert_eq!(
+            Err(QueryError::ParseError("variable is written twice in row: a".to_string())),
+            memory_table.insert_row(vec![("a", DataType::Int(1)), ("a", DataType::Int(2))])
+        );
+        assert_eq!(4, memory_table.get_logic_time());
+        assert_eq!(Some(&DataType::Int(4)), memory_table.get_var("a").unwrap().get_last_value());
+    }
+
+    #[test]
+    fn test_memory_table_read_at() {
+        let mut memory_table = MemoryTable::init();
+        memory_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        memory_table.insert("a", DataType::Int(3)).unwrap();
+        memory_table.insert("b", DataType::Int(4)).unwrap();
+        memory_table.insert("a", DataType::Int(1)).unwrap();
+
+        debug_assert_eq!(
+            vec![
+                PrintOfState::new(&"a".to_string(), vec![DataType::Int(3)]),
+                PrintOfState::new(&"b".to_string(), vec![DataType::Int(4)]),
+            ],
+            memory_table.read_at(2)
+        );
+        debug_assert_eq!(
+            vec![
+                PrintOfState::new(
+                    &"a".to_string(),
+                    vec![DataType::Int(1), DataType::Int(3), DataType::Int(1)]
+                ),
+                PrintOfState::new(&"b".to_string(), vec![DataType::Int(2), DataType::Int(4)]),
+            ],
+            memory_table.read_between(0, 3)
+        );
+        debug_assert!(memory_table.read_at(10).is_empty());
+    }
+
+    #[test]
+    fn test_memory_table_compact() {
+        let mut memory_table = MemoryTable::init();
+        for a in 1..=4 {
+            memory_table.insert("a", DataType::Int(a)).unwrap();
+        }
+
+        // watermark before start of logic time keeps full history
+        memory_table.set_retention(Some(RetentionPolicy::Watermark(i64::MIN)));
+        memory_table.compact();
+        debug_assert_eq!(
+            vec![PrintOfState::new(&"a".to_string(), (1..=4).map(DataType::Int).collect())],
+            memory_table.read_between(0, 3)
+        );
+
+        memory_table.set_retention(Some(RetentionPolicy::Ticks(2)));
+        memory_table.compact();
+        debug_assert_eq!(
+            vec![PrintOfState::new(&"a".to_string(), vec![DataType::Int(3), DataType::Int(4)])],
+            memory_table.read_between(0, 3)
+        );
+
+        // policy is applied by every write, read right after it sees only kept history
+        memory_table.insert("a", DataType::Int(5)).unwrap();
+        assert_eq!(
+            vec![PrintOfState::new(&"a".to_string(), vec![DataType::Int(4), DataType::Int(5)])],
+            memory_table.read_between(0, 4)
+        );
+        memory_table.insert_row(vec![("a", DataType::Int(6)), ("b", DataType::Int(1))]).unwrap();
+        memory_table.insert("b", DataType::Int(2)).unwrap();
+        assert_eq!(
+            vec![
+                PrintOfState::new(&"a".to_string(), vec![DataType::Int(6)]),
+                PrintOfState::new(&"b".to_string(), vec![DataType::Int(1), DataType::Int(2)]),
+            ],
+            memory_table.read_between(0, 6)
+        );
+
+        let mut memory_table = MemoryTable::init();
+        memory_table.set_retention(Some(RetentionPolicy::Values(2)));
+        for a in &[1, 2, 1, 3] {
+            memory_table.insert("a", DataType::Int(*a)).unwrap();
+        }
+        assert_eq!(
+            vec![PrintOfState::new(&"a".to_string(), vec![DataType::Int(1), DataType::Int(1), DataType::Int(3)])],
+            memory_table.read_between(0, 3)
+        );
+
+        let mut memory_table = MemoryTable::init();
+        memory_table.set_retention(Some(RetentionPolicy::Watermark(2)));
+        for a in 1..=3 {
+            memory_table.insert("a", DataType::Int(a)).unwrap();
+        }
+        assert_eq!(
+            vec![PrintOfState::new(&"a".to_string(), vec![DataType::Int(3)])],
+            memory_table.read_between(0, 2)
+        );
+    }
+
+    #[test]
+    fn test_memory_table_get_row_table() {
+        let mut memory_table = MemoryTable::init();
+        memory_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        memory_table.insert("a", DataType::Int(3)).unwrap();
+        memory_table.insert("a", DataType::Int(1)).unwrap();
+
+        let row_table = memory_table.get_row_table(1);
+        debug_assert_eq!(1, row_table.get_logic_time());
+        debug_assert_eq!(
+            vec![
+                PrintOfState::new(&"a".to_string(), vec![DataType::Int(3)]),
+                PrintOfState::new(&"b".to_string(), vec![DataType::Int(2)]),
+            ],
+            row_table.read_at(0)
+        );
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Int(1),
+            "==".to_string(),
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::new()),
+            row_table.find_indexes_by_condition(&Predicate::Expr(&binary_expr))
+        );
+    }
+
+    #[test]
+    fn test_memory_table_find_rows_by_condition() {
+        let mut memory_table = MemoryTable::init();
+        memory_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        memory_table.insert("a", DataType::Int(3)).unwrap();
+        memory_table.insert("b", DataType::Int(4)).unwrap();
+        memory_table.insert("a", DataType::Int(1)).unwrap();
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Int(1),
+            "==".to_string(),
+        );
+        let result_set = memory_table
+            .find_rows_by_condition(&Predicate::all(vec![&binary_expr]), None)
+            .unwrap();
+        debug_assert_eq!(&["a".to_string(), "b".to_string()], result_set.get_columns());
+        debug_assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(2)]),
+                Row::new(3..=3, vec![DataType::Int(1), DataType::Int(4)]),
+            ],
+            result_set.get_rows()
+        );
+
+        let window = IntervalSet::from_range(1..=2);
+        let result_set = memory_table
+            .find_rows_by_condition(&Predicate::all(Vec::new()), Some(&window))
+            .unwrap();
+        debug_assert_eq!(
+            &[
+                Row::new(1..=1, vec![DataType::Int(3), DataType::Int(2)]),
+                Row::new(2..=2, vec![DataType::Int(3), DataType::Int(4)]),
+            ],
+            result_set.get_rows()
+        );
+
+        // deleted logic times have no rows
+        memory_table.delete_by_predicate(&Predicate::all(vec![&binary_expr])).unwrap();
+        let result_set = memory_table
+            .find_rows_by_condition(&Predicate::all(Vec::new()), None)
+            .unwrap();
+        debug_assert_eq!(
+            &[
+                Row::new(1..=1, vec![DataType::Int(3), DataType::Int(2)]),
+                Row::new(2..=2, vec![DataType::Int(3), DataType::Int(4)]),
+            ],
+            result_set.get_rows()
+        );
+    }
+
+    #[test]
+    fn test_memory_table_find_projected_rows_by_condition() {
+        let mut memory_table = MemoryTable::init();
+        memory_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        memory_table.insert("b", DataType::Int(3)).unwrap();
+        memory_table.insert("a", DataType::Int(4)).unwrap();
+
+        let result_set = memory_table
+            .find_projected_rows_by_condition(&Predicate::all(Vec::new()), None, &["a".to_string()])
+            .unwrap();
+        debug_assert_eq!(&["a".to_string()], result_set.get_columns());
+        debug_assert_eq!(
+            &[
+                Row::new(0..=1, vec![DataType::Int(1)]),
+                Row::new(2..=2, vec![DataType::Int(4)]),
+            ],
+            result_set.get_rows()
+        );
+
+        debug_assert_eq!(
+            Some(QueryError::UnknownVariable("c".to_string())),
+            memory_table
+                .find_projected_rows_by_condition(&Predicate::all(Vec::new()), None, &["c".to_string()])
+                .err()
+        );
     }
 
     #[test]
     fn test_memory_table_find() {
         let mut memory_table = MemoryTable::init();
-        memory_table.insert("my_var", DataType::Text("mytext".to_string()));
-        memory_table.insert("my_var", DataType::Null);
-        memory_table.insert("my_var2", DataType::Null);
+        memory_table.insert("my_var", DataType::Text("mytext".to_string())).unwrap();
+        memory_table.insert("my_var", DataType::Null).unwrap();
+        memory_table.insert("my_var2", DataType::Null).unwrap();
         let data_var = DataVar::new("my_var".to_string(), DataType::Null);
-        let a = memory_table.find(&data_var);
-        println!("{:?}", a);
+        assert_eq!(Some(vec![DataType::Null]), memory_table.find(&data_var));
+        let data_var = DataVar::new("my_var2".to_string(), DataType::Null);
+        assert_eq!(Some(vec![DataType::Null]), memory_table.find(&data_var));
+        let data_var = DataVar::new("my_var".to_string(), DataType::Int(1));
+        assert_eq!(None, memory_table.find(&data_var));
     }
 
     #[test]
     fn test_memory_find_by_predicate() {
         let mut mem_table = MemoryTable::init();
-        mem_table.insert("my_val", DataType::Int(101));
-        mem_table.insert("my_val", DataType::Int(101));
-        mem_table.insert("my_val2", DataType::Int(64));
-        mem_table.insert("my_val2", DataType::Int(32));
-        mem_table.insert("my_val3", DataType::Int(32));
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+        mem_table.insert("my_val2", DataType::Int(64)).unwrap();
+        mem_table.insert("my_val2", DataType::Int(32)).unwrap();
+        mem_table.insert("my_val3", DataType::Int(32)).unwrap();
 
         let binary_expr = BinaryExpr::new(
             DataType::Int(101),
@@ -232,45 +1013,36 @@ mod test {
             "==".to_string(),
         );
 
-        let vec_print_of_state = mem_table.find_by_predicate(&binary_expr).unwrap();
+        let vec_print_of_state = mem_table.find_by_predicate(&binary_expr).unwrap().unwrap();
 
         println!("{:#?}", vec_print_of_state);
 
-        debug_assert_eq!(
-            true,
-            vec_print_of_state.contains(&PrintOfState::new(
-                &"my_val".to_string(),
-                vec![DataType::Int(101)]
-            ))
-        );
-        debug_assert_eq!(
-            true,
-            vec_print_of_state.contains(&PrintOfState::new(
-                &"my_val2".to_string(),
-                vec![DataType::Int(32), DataType::Int(64)]
-            ))
-        );
-        debug_assert_eq!(
-            true,
-            vec_print_of_state.contains(&PrintOfState::new(
-                &"my_val3".to_string(),
-                vec![DataType::Int(32)]
-            ))
-        );
+        debug_assert!(vec_print_of_state.contains(&PrintOfState::new(
+            &"my_val".to_string(),
+            vec![DataType::Int(101)]
+        )));
+        debug_assert!(vec_print_of_state.contains(&PrintOfState::new(
+            &"my_val2".to_string(),
+            vec![DataType::Int(32), DataType::Int(64)]
+        )));
+        debug_assert!(vec_print_of_state.contains(&PrintOfState::new(
+            &"my_val3".to_string(),
+            vec![DataType::Int(32)]
+        )));
     }
 
     #[test]
     fn test_memory_find_by_predicate_intense() {
         let mut mem_table = MemoryTable::init();
-        mem_table.insert("my_val", DataType::Int(101));
-        mem_table.insert("my_val", DataType::Int(101));
-        mem_table.insert("my_val", DataType::Int(64));
-        mem_table.insert("my_val2", DataType::Int(32));
-        mem_table.insert("my_val", DataType::Int(101));
-        mem_table.insert("my_val3", DataType::Int(32));
-        mem_table.insert("my_val3", DataType::Int(64));
-        mem_table.insert("my_val3", DataType::Int(89));
-        mem_table.insert("my_val3", DataType::Int(90));
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+        mem_table.insert("my_val", DataType::Int(64)).unwrap();
+        mem_table.insert("my_val2", DataType::Int(32)).unwrap();
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+        mem_table.insert("my_val3", DataType::Int(32)).unwrap();
+        mem_table.insert("my_val3", DataType::Int(64)).unwrap();
+        mem_table.insert("my_val3", DataType::Int(89)).unwrap();
+        mem_table.insert("my_val3", DataType::Int(90)).unwrap();
 
         let binary_expr = BinaryExpr::new(
             DataType::Int(101),
@@ -282,4 +1054,72 @@ mod test {
 
         println!("{:#?}", vec_print_of_state);
     }
+
+    #[test]
+    fn test_memory_table_find_indexes_by_variables() {
+        let mut mem_table = MemoryTable::init();
+        mem_table.insert_row(vec![("a", DataType::Int(1)), ("b", DataType::Int(2))]).unwrap();
+        mem_table.insert("a", DataType::Int(3)).unwrap();
+        mem_table.insert("b", DataType::Int(4)).unwrap();
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Symbol("b".to_string()),
+            ">".to_string(),
+        );
+        debug_assert_eq!(
+            Ok(Some(IntervalSet::from(vec![1..=1]))),
+            mem_table.find_indexes_by_predicate(&binary_expr)
+        );
+
+        // unknown variable is error for variable operand as for value operand
+        for (left, right) in [
+            (DataType::Symbol("a".to_string()), DataType::Symbol("c".to_string())),
+            (DataType::Symbol("c".to_string()), DataType::Symbol("a".to_string())),
+            (DataType::Symbol("c".to_string()), DataType::Int(1)),
+            (DataType::Int(1), DataType::Symbol("c".to_string())),
+        ] {
+            let binary_expr = BinaryExpr::new(left, right, ">".to_string());
+            debug_assert_eq!(
+                Err(QueryError::UnknownVariable("c".to_string())),
+                mem_table.find_indexes_by_predicate(&binary_expr)
+            );
+        }
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("a".to_string()),
+            DataType::Symbol("b".to_string()),
+            "like".to_string(),
+        );
+        debug_assert_eq!(
+            Err(QueryError::UnsupportedOperator("like".to_string())),
+            mem_table.find_indexes_by_predicate(&binary_expr)
+        );
+    }
+
+    #[test]
+    fn test_memory_find_by_predicate_errors() {
+        let mut mem_table = MemoryTable::init();
+        mem_table.insert("my_val", DataType::Int(101)).unwrap();
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("unknown_val".to_string()),
+            DataType::Int(101),
+            "==".to_string(),
+        );
+        debug_assert_eq!(
+            Err(QueryError::UnknownVariable("unknown_val".to_string())),
+            mem_table.find_by_predicate_intense(&binary_expr)
+        );
+
+        let binary_expr = BinaryExpr::new(
+            DataType::Symbol("my_val".to_string()),
+            DataType::Int(101),
+            "=~".to_string(),
+        );
+        debug_assert_eq!(
+            Err(QueryError::UnsupportedOperator("=~".to_string())),
+            mem_table.find_by_predicate_intense(&binary_expr)
+        );
+    }
 }
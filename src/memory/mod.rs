@@ -1,6 +1,13 @@
+pub mod aggregate;
 mod compared;
 mod intersection;
 pub mod memory_channel;
-mod memory_machine;
+pub mod memory_machine;
 pub mod memory_table;
+pub mod operator;
+pub mod predicate;
 pub mod print_of_state;
+pub mod result_set;
+pub mod retention_policy;
+pub mod schema;
+pub mod subscription;
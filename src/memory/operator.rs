@@ -0,0 +1,315 @@
+use crate::memory::compared::Compared;
+use crate::memory::memory_machine::{IntervalSet, MemoryMachine};
+use crate::error::query_error::QueryError;
+use qdb_ast::ast::types::DataType;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Operator of predicate: x op operands.
+// Returns indexes of values of variable which satisfy operator.
+pub trait Operator: Send + Sync {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError>;
+}
+
+// To check count of operands of operator
+fn check_operands_count(name: &str, operands: &[DataType], count: usize) -> Result<(), QueryError> {
+    if operands.len() != count {
+        return Err(QueryError::ParseError(format!(
+            "{} requires {} operand(s), found {}",
+            name,
+            count,
+            operands.len()
+        )));
+    }
+    Ok(())
+}
+
+fn get_text<'a>(name: &str, operand: &'a DataType) -> Result<&'a str, QueryError> {
+    match operand {
+        DataType::Text(text) => Ok(text),
+        _ => Err(QueryError::TypeMismatch(format!(
+            "{} requires text pattern, found {:?}",
+            name, operand
+        ))),
+    }
+}
+
+type OrderingPredicate = fn(&Ordering) -> bool;
+
+// predicates of orderings accepted by ordering operators
+fn get_ordering_operators() -> [(&'static str, OrderingPredicate); 6] {
+    [
+        ("==", <DataType as Compared>::eq),
+        ("!=", <DataType as Compared>::neq),
+        (">=", <DataType as Compared>::eq_or_gr),
+        (">", <DataType as Compared>::gr),
+        ("<=", <DataType as Compared>::eq_or_le),
+        ("<", <DataType as Compared>::le),
+    ]
+}
+
+// To get predicate of ordering operator by name, e.g. to compare two variables
+pub fn get_ordering_predicate(name: &str) -> Option<fn(&Ordering) -> bool> {
+    get_ordering_operators()
+        .iter()
+        .find(|(operator, _)| *operator == name)
+        .map(|(_, predicate)| *predicate)
+}
+
+// x == a, x != a, x >= a, x > a, x <= a, x < a
+pub struct OrderingOperator {
+    name: &'static str,
+    predicate: fn(&Ordering) -> bool,
+}
+
+impl Operator for OrderingOperator {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        check_operands_count(self.name, operands, 1)?;
+        mem_machine.get_by_ordering(&operands[0], self.predicate)
+    }
+}
+
+// x in (a, b, c) := x == a ⋁ x == b ⋁ x == c
+pub struct InOperator;
+
+impl Operator for InOperator {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        if operands.is_empty() {
+            return Err(QueryError::ParseError("in requires at least one operand".to_string()));
+        }
+        let mut vec: Vec<IntervalSet> = Vec::new();
+        for operand in operands {
+            vec.extend(mem_machine.get_by_ordering(operand, <DataType as Compared>::eq)?);
+        }
+        Ok(vec)
+    }
+}
+
+// x between a and b := x >= a ⋀ x <= b
+pub struct BetweenOperator;
+
+impl Operator for BetweenOperator {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        check_operands_count("between", operands, 2)?;
+        let union = |vec: Vec<IntervalSet>| -> IntervalSet { vec.into_iter().flatten().collect() };
+        let from = union(mem_machine.get_by_ordering(&operands[0], <DataType as Compared>::eq_or_gr)?);
+        let to = union(mem_machine.get_by_ordering(&operands[1], <DataType as Compared>::eq_or_le)?);
+        Ok(vec![from.intersection(&to)])
+    }
+}
+
+// x like "abc%": '%' - any sequence of symbols, '_' - any one symbol
+pub struct LikeOperator;
+
+impl LikeOperator {
+    // wildcard matching with backtracking to last '%'
+    pub fn is_match(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        let (mut i, mut j) = (0, 0);
+        let mut last_any: Option<(usize, usize)> = None;
+        while j < text.len() {
+            if i < pattern.len() && (pattern[i] == '_' || pattern[i] == text[j]) {
+                i += 1;
+                j += 1;
+            } else if i < pattern.len() && pattern[i] == '%' {
+                last_any = Some((i, j));
+                i += 1;
+            } else if let Some((any_i, any_j)) = last_any {
+                // '%' takes one more symbol
+                last_any = Some((any_i, any_j + 1));
+                i = any_i + 1;
+                j = any_j + 1;
+            } else {
+                return false;
+            }
+        }
+        pattern[i..].iter().all(|symbol| *symbol == '%')
+    }
+}
+
+impl Operator for LikeOperator {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        check_operands_count("like", operands, 1)?;
+        let pattern = get_text("like", &operands[0])?;
+        mem_machine.get_by_compare_with(&operands[0], |this, _| match this {
+            DataType::Text(text) => Self::is_match(pattern, text),
+            _ => false,
+        })
+    }
+}
+
+// x ~ "regex": text contains match of regular expression
+pub struct RegexOperator;
+
+impl Operator for RegexOperator {
+    fn get_indexes(
+        &self,
+        mem_machine: &MemoryMachine,
+        operands: &[DataType],
+    ) -> Result<Vec<IntervalSet>, QueryError> {
+        check_operands_count("~", operands, 1)?;
+        let regex = Regex::new(get_text("~", &operands[0])?)
+            .map_err(|error| QueryError::ParseError(format!("bad regular expression: {}", error)))?;
+        mem_machine.get_by_compare_with(&operands[0], |this, _| match this {
+            DataType::Text(text) => regex.is_match(text),
+            _ => false,
+        })
+    }
+}
+
+// registry shared by all tables, it's fixed after first use
+static REGISTRY: OnceLock<OperatorRegistry> = OnceLock::new();
+
+// Operators of predicates by name.
+// New operator is added by registration, evaluation of predicates doesn't change:
+// registry with own operators is installed by OperatorRegistry::install before first query.
+pub struct OperatorRegistry {
+    operators: HashMap<String, Box<dyn Operator>>,
+}
+
+impl OperatorRegistry {
+    // public function
+    pub fn new() -> Self {
+        OperatorRegistry {
+            operators: HashMap::new(),
+        }
+    }
+
+    // registry shared by all tables, built-in operators if other registry isn't installed
+    pub fn global() -> &'static OperatorRegistry {
+        REGISTRY.get_or_init(OperatorRegistry::default)
+    }
+
+    // To install registry shared by all tables, e.g. default registry with own operators.
+    // Returns registry back if shared registry is already installed or used.
+    pub fn install(registry: OperatorRegistry) -> Result<(), OperatorRegistry> {
+        REGISTRY.set(registry)
+    }
+
+    pub fn register(&mut self, name: &str, operator: Box<dyn Operator>) {
+        self.operators.insert(name.to_string(), operator);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.operators.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&dyn Operator, QueryError> {
+        self.operators
+            .get(name)
+            .map(|operator| operator.as_ref())
+            .ok_or_else(|| QueryError::UnsupportedOperator(name.to_string()))
+    }
+}
+
+impl Default for OperatorRegistry {
+    fn default() -> Self {
+        let mut registry = OperatorRegistry::new();
+        for (name, predicate) in get_ordering_operators().iter() {
+            registry.register(
+                name,
+                Box::new(OrderingOperator {
+                    name,
+                    predicate: *predicate,
+                }),
+            );
+        }
+        registry.register("in", Box::new(InOperator));
+        registry.register("between", Box::new(BetweenOperator));
+        registry.register("like", Box::new(LikeOperator));
+        registry.register("~", Box::new(RegexOperator));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_machine::{IntervalSet, MemoryMachine};
+    use crate::memory::operator::{LikeOperator, OperatorRegistry};
+    use crate::error::query_error::QueryError;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_like_operator_is_match() {
+        debug_assert!(LikeOperator::is_match("abc%", "abcdef"));
+        debug_assert!(LikeOperator::is_match("%def", "abcdef"));
+        debug_assert!(LikeOperator::is_match("a_c%f", "abcdef"));
+        debug_assert!(LikeOperator::is_match("%", ""));
+        debug_assert!(!LikeOperator::is_match("abc", "abcdef"));
+        debug_assert!(!LikeOperator::is_match("%x%", "abcdef"));
+    }
+
+    #[test]
+    fn test_operator_registry() {
+        let mut memory_machine = MemoryMachine::init();
+        memory_machine.insert(DataType::Text("apple".to_string()));
+        memory_machine.insert(DataType::Text("banana".to_string()));
+        memory_machine.insert(DataType::Text("apricot".to_string()));
+
+        let registry = OperatorRegistry::global();
+        let get_indexes = |name: &str, operands: Vec<DataType>| -> Result<IntervalSet, QueryError> {
+            let vec = registry.get(name)?.get_indexes(&memory_machine, &operands)?;
+            Ok(vec.into_iter().flatten().collect())
+        };
+
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![0..=0, 2..=2])),
+            get_indexes("like", vec![DataType::Text("ap%".to_string())])
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![1..=2])),
+            get_indexes("~", vec![DataType::Text("an|co".to_string())])
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![0..=1])),
+            get_indexes(
+                "in",
+                vec![DataType::Text("apple".to_string()), DataType::Text("banana".to_string())]
+            )
+        );
+        debug_assert_eq!(
+            Ok(IntervalSet::from(vec![0..=0, 2..=2])),
+            get_indexes(
+                "between",
+                vec![DataType::Text("a".to_string()), DataType::Text("b".to_string())]
+            )
+        );
+        debug_assert!(matches!(
+            get_indexes("~", vec![DataType::Text("(".to_string())]),
+            Err(QueryError::ParseError(_))
+        ));
+        debug_assert_eq!(
+            Err(QueryError::UnsupportedOperator("=~".to_string())),
+            get_indexes("=~", vec![DataType::Text("a".to_string())])
+        );
+
+        // shared registry is fixed after first use
+        let mut other = OperatorRegistry::default();
+        other.register("=~", Box::new(LikeOperator));
+        debug_assert!(OperatorRegistry::install(other).is_err());
+        debug_assert!(!OperatorRegistry::global().contains("=~"));
+    }
+}
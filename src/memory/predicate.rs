@@ -0,0 +1,163 @@
+use qdb_ast::ast::types::{BinaryExpr, DataType};
+
+// Expression of variable and operator with any count of operands,
+// for operators which binary expression can't hold: x in (1, 2, 3)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    name: String,
+    operator: String,
+    operands: Vec<DataType>,
+}
+
+impl Operation {
+    pub fn new(name: &str, operator: &str, operands: Vec<DataType>) -> Self {
+        Operation {
+            name: name.to_string(),
+            operator: operator.to_string(),
+            operands,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_operator(&self) -> &str {
+        &self.operator
+    }
+
+    pub fn get_operands(&self) -> &[DataType] {
+        &self.operands
+    }
+}
+
+// Boolean composition of binary expressions.
+// Each expression is evaluated to set of logic time intervals,
+// then sets are combined: And - ⋂, Or - ⋃, Not - complement
+// within logic time of table.
+pub enum Predicate<'a> {
+    Expr(&'a BinaryExpr),
+    Operation(&'a Operation),
+    And(Vec<Predicate<'a>>),
+    Or(Vec<Predicate<'a>>),
+    Not(Box<Predicate<'a>>),
+}
+
+impl<'a> Predicate<'a> {
+    // To combine expressions as conjunction: a, b := a ⋀ b
+    pub fn all(binary_exprs: Vec<&'a BinaryExpr>) -> Self {
+        Predicate::And(binary_exprs.into_iter().map(Predicate::Expr).collect())
+    }
+
+    // To combine expressions as disjunction: a ⋁ b
+    pub fn any(binary_exprs: Vec<&'a BinaryExpr>) -> Self {
+        Predicate::Or(binary_exprs.into_iter().map(Predicate::Expr).collect())
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(predicate: Predicate<'a>) -> Self {
+        Predicate::Not(Box::new(predicate))
+    }
+}
+
+// Owned composition of expressions, for condition which parser can't hold
+// (disjunction, negation) or which outlives query (condition of subscription).
+// It's evaluated as predicate which borrows it.
+#[derive(Debug)]
+pub enum Condition {
+    Expr(BinaryExpr),
+    Operation(Operation),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn to_predicate(&self) -> Predicate<'_> {
+        match self {
+            Condition::Expr(binary_expr) => Predicate::Expr(binary_expr),
+            Condition::Operation(operation) => Predicate::Operation(operation),
+            Condition::And(conditions) => Predicate::And(conditions.iter().map(Condition::to_predicate).collect()),
+            Condition::Or(conditions) => Predicate::Or(conditions.iter().map(Condition::to_predicate).collect()),
+            Condition::Not(condition) => Predicate::not(condition.to_predicate()),
+        }
+    }
+
+    // To get names of variables of condition, names may be qualified by channel
+    pub fn get_names(&self) -> Vec<&str> {
+        match self {
+            Condition::Expr(binary_expr) => {
+                let (left, right, _) = binary_expr.get();
+                [left, right]
+                    .iter()
+                    .filter_map(|data_type| match data_type {
+                        DataType::Symbol(symbol) => Some(symbol.as_str()),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            Condition::Operation(operation) => vec![operation.get_name()],
+            Condition::And(conditions) | Condition::Or(conditions) => {
+                conditions.iter().flat_map(Condition::get_names).collect()
+            }
+            Condition::Not(condition) => condition.get_names(),
+        }
+    }
+}
+
+impl From<&BinaryExpr> for Condition {
+    fn from(binary_expr: &BinaryExpr) -> Self {
+        let (left, right, operator) = binary_expr.get();
+        Condition::Expr(BinaryExpr::new(left.clone(), right.clone(), operator.to_string()))
+    }
+}
+
+// binary expression of parser isn't cloneable, it's copied by parts
+impl Clone for Condition {
+    fn clone(&self) -> Self {
+        match self {
+            Condition::Expr(binary_expr) => Condition::from(binary_expr),
+            Condition::Operation(operation) => Condition::Operation(operation.clone()),
+            Condition::And(conditions) => Condition::And(conditions.clone()),
+            Condition::Or(conditions) => Condition::Or(conditions.clone()),
+            Condition::Not(condition) => Condition::Not(condition.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_machine::IntervalSet;
+    use crate::memory::memory_table::MemoryTable;
+    use crate::memory::predicate::Predicate;
+    use qdb_ast::ast::types::{BinaryExpr, DataType};
+
+    #[test]
+    fn test_predicate_composition() {
+        let mut mem_table = MemoryTable::init();
+        mem_table.insert_row(vec![("c", DataType::Int(1)), ("d", DataType::Int(10))]).unwrap();
+        mem_table.insert_row(vec![("c", DataType::Int(2)), ("d", DataType::Int(3))]).unwrap();
+        mem_table.insert_row(vec![("c", DataType::Int(-1)), ("d", DataType::Int(4))]).unwrap();
+        mem_table.insert_row(vec![("c", DataType::Int(5)), ("d", DataType::Int(20))]).unwrap();
+
+        let c_gr_0 = BinaryExpr::new(
+            DataType::Symbol("c".to_string()),
+            DataType::Int(0),
+            ">".to_string(),
+        );
+        let d_le_5 = BinaryExpr::new(
+            DataType::Symbol("d".to_string()),
+            DataType::Int(5),
+            "<".to_string(),
+        );
+
+        let and = Predicate::all(vec![&c_gr_0, &d_le_5]);
+        debug_assert_eq!(IntervalSet::from(vec![1..=1]), mem_table.find_indexes_by_condition(&and).unwrap());
+
+        let or = Predicate::any(vec![&c_gr_0, &d_le_5]);
+        debug_assert_eq!(IntervalSet::from(vec![0..=3]), mem_table.find_indexes_by_condition(&or).unwrap());
+
+        let not = Predicate::not(Predicate::Expr(&c_gr_0));
+        debug_assert_eq!(IntervalSet::from(vec![2..=2]), mem_table.find_indexes_by_condition(&not).unwrap());
+    }
+}
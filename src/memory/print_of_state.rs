@@ -1,6 +1,6 @@
 use qdb_ast::ast::types::DataType;
 
-#[derive(Debug, PartialOrd, PartialEq)]
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct PrintOfState {
     name: String,
     values: Vec<DataType>,
@@ -13,4 +13,12 @@ impl PrintOfState {
             values,
         }
     }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_values(&self) -> &[DataType] {
+        &self.values
+    }
 }
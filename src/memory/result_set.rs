@@ -0,0 +1,205 @@
+use crate::memory::memory_machine::IntervalSet;
+use qdb_ast::ast::types::DataType;
+use std::ops::RangeInclusive;
+
+// One row of result: values of columns during contiguous range of logic time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    logic_times: RangeInclusive<i64>,
+    values: Vec<DataType>,
+}
+
+impl Row {
+    pub fn new(logic_times: RangeInclusive<i64>, values: Vec<DataType>) -> Self {
+        Row { logic_times, values }
+    }
+
+    pub fn get_logic_times(&self) -> &RangeInclusive<i64> {
+        &self.logic_times
+    }
+
+    pub fn get_values(&self) -> &[DataType] {
+        &self.values
+    }
+
+    pub fn get_value(&self, column_index: usize) -> Option<&DataType> {
+        self.values.get(column_index)
+    }
+}
+
+// Rows in order of logic time, every row has one value per column.
+// Variable without value at logic time of row is Null.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultSet {
+    columns: Vec<String>,
+    rows: Vec<Row>,
+}
+
+impl ResultSet {
+    // public function
+    pub fn new(columns: Vec<String>) -> Self {
+        ResultSet {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn get_columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn get_rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn get_column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    // To add row, row which continues last one with same values extends it
+    pub fn push_row(&mut self, row: Row) {
+        if let Some(last) = self.rows.last_mut() {
+            if last.logic_times.end() + 1 == *row.logic_times.start() && last.values == row.values {
+                last.logic_times = *last.logic_times.start()..=*row.logic_times.end();
+                return;
+            }
+        }
+        self.rows.push(row);
+    }
+
+    // To keep parts of rows inside logic times of window
+    pub fn restrict(self, window: &IntervalSet) -> ResultSet {
+        let mut result_set = ResultSet::new(self.columns);
+        for row in self.rows {
+            let logic_times = IntervalSet::from_range(row.logic_times).intersection(window);
+            for range in logic_times.iter() {
+                result_set.push_row(Row::new(range.clone(), row.values.clone()));
+            }
+        }
+        result_set
+    }
+
+    // To keep only named columns, rows which continue each other with same values are merged.
+    // Returns None if result set has no such column.
+    pub fn project(self, columns: &[String]) -> Option<ResultSet> {
+        let indexes = columns
+            .iter()
+            .map(|column| self.get_column_index(column))
+            .collect::<Option<Vec<usize>>>()?;
+        let mut result_set = ResultSet::new(columns.to_vec());
+        for row in self.rows {
+            let values = indexes.iter().map(|index| row.values[*index].clone()).collect();
+            result_set.push_row(Row::new(row.logic_times, values));
+        }
+        Some(result_set)
+    }
+
+    // To qualify columns by name of channel: c -> my_node.c
+    pub fn qualify(mut self, channel_name: &str) -> ResultSet {
+        for column in self.columns.iter_mut() {
+            *column = format!("{}.{}", channel_name, column);
+        }
+        self
+    }
+
+    // To merge rows of other result set (e.g. of other channel) into own rows by logic time,
+    // own row goes first at same logic time. Columns are merged by name and missing values are Null.
+    pub fn append(&mut self, other: ResultSet) {
+        let column_indexes: Vec<usize> = other
+            .columns
+            .iter()
+            .map(|column| match self.get_column_index(column) {
+                Some(index) => index,
+                None => {
+                    self.columns.push(column.to_string());
+                    for row in self.rows.iter_mut() {
+                        row.values.push(DataType::Null);
+                    }
+                    self.columns.len() - 1
+                }
+            })
+            .collect();
+
+        let mut own_rows = std::mem::take(&mut self.rows).into_iter().peekable();
+        for row in other.rows {
+            let start = *row.logic_times.start();
+            while let Some(own_row) = own_rows.next_if(|own_row| *own_row.logic_times.start() <= start) {
+                self.rows.push(own_row);
+            }
+            let mut values = vec![DataType::Null; self.columns.len()];
+            for (value, index) in row.values.into_iter().zip(column_indexes.iter()) {
+                values[*index] = value;
+            }
+            self.rows.push(Row::new(row.logic_times, values));
+        }
+        self.rows.extend(own_rows);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_machine::IntervalSet;
+    use crate::memory::result_set::{ResultSet, Row};
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_result_set() {
+        let mut result_set = ResultSet::new(vec!["a".to_string(), "b".to_string()]);
+        result_set.push_row(Row::new(0..=0, vec![DataType::Int(1), DataType::Null]));
+        result_set.push_row(Row::new(1..=2, vec![DataType::Int(1), DataType::Null]));
+        result_set.push_row(Row::new(4..=4, vec![DataType::Int(1), DataType::Null]));
+        debug_assert_eq!(2, result_set.get_rows().len());
+        debug_assert_eq!(&(0..=2), result_set.get_rows()[0].get_logic_times());
+
+        let mut other = ResultSet::new(vec!["c".to_string(), "a".to_string()]);
+        other.push_row(Row::new(0..=0, vec![DataType::Int(3), DataType::Int(2)]));
+        result_set.append(other);
+        debug_assert_eq!(&["a".to_string(), "b".to_string(), "c".to_string()], result_set.get_columns());
+        debug_assert_eq!(
+            &Row::new(0..=2, vec![DataType::Int(1), DataType::Null, DataType::Null]),
+            &result_set.get_rows()[0]
+        );
+        debug_assert_eq!(
+            &Row::new(0..=0, vec![DataType::Int(2), DataType::Null, DataType::Int(3)]),
+            &result_set.get_rows()[1]
+        );
+        debug_assert_eq!(&(4..=4), result_set.get_rows()[2].get_logic_times());
+
+        let result_set = result_set.qualify("my_node");
+        debug_assert_eq!(
+            &["my_node.a".to_string(), "my_node.b".to_string(), "my_node.c".to_string()],
+            result_set.get_columns()
+        );
+        debug_assert_eq!(3, result_set.get_rows().len());
+    }
+
+    #[test]
+    fn test_result_set_restrict_and_project() {
+        let mut result_set = ResultSet::new(vec!["a".to_string(), "b".to_string()]);
+        result_set.push_row(Row::new(0..=3, vec![DataType::Int(1), DataType::Int(1)]));
+        result_set.push_row(Row::new(4..=6, vec![DataType::Int(1), DataType::Int(2)]));
+
+        let mut window = IntervalSet::from_range(2..=4);
+        window.insert(6..=8);
+        let result_set = result_set.restrict(&window);
+        debug_assert_eq!(
+            &[
+                Row::new(2..=3, vec![DataType::Int(1), DataType::Int(1)]),
+                Row::new(4..=4, vec![DataType::Int(1), DataType::Int(2)]),
+                Row::new(6..=6, vec![DataType::Int(1), DataType::Int(2)]),
+            ],
+            result_set.get_rows()
+        );
+
+        debug_assert_eq!(None, result_set.clone().project(&["c".to_string()]));
+        let result_set = result_set.project(&["a".to_string()]).unwrap();
+        debug_assert_eq!(
+            &[Row::new(2..=4, vec![DataType::Int(1)]), Row::new(6..=6, vec![DataType::Int(1)])],
+            result_set.get_rows()
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Which part of history of variables is kept by compaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetentionPolicy {
+    // last given count of logic ticks of table
+    Ticks(i64),
+    // last given count of distinct values of each variable
+    Values(usize),
+    // logic times from watermark, watermark isn't negative as logic time starts from 0
+    Watermark(i64),
+}
+
+impl FromStr for RetentionPolicy {
+    type Err = String;
+
+    // ticks:<count> | values:<count> | watermark:<logic time>
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid retention policy: {}", value);
+        let (name, number) = value.split_once(':').ok_or_else(error)?;
+        match name.trim() {
+            "ticks" => number.trim().parse().ok().filter(|count| *count > 0).map(RetentionPolicy::Ticks),
+            "values" => number.trim().parse().ok().filter(|count| *count > 0).map(RetentionPolicy::Values),
+            "watermark" => number
+                .trim()
+                .parse()
+                .ok()
+                .filter(|logic_time| *logic_time >= 0)
+                .map(RetentionPolicy::Watermark),
+            _ => None,
+        }
+        .ok_or_else(error)
+    }
+}
+
+impl fmt::Display for RetentionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionPolicy::Ticks(count) => write!(f, "ticks:{}", count),
+            RetentionPolicy::Values(count) => write!(f, "values:{}", count),
+            RetentionPolicy::Watermark(logic_time) => write!(f, "watermark:{}", logic_time),
+        }
+    }
+}
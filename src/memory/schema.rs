@@ -0,0 +1,193 @@
+use crate::error::query_error::QueryError;
+use qdb_ast::ast::types::DataType;
+use std::collections::BTreeMap;
+
+// Declared type of variable, null is allowed for any type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Int,
+    Real,
+    Text,
+    Symbol,
+}
+
+impl ValueType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(ValueType::Int),
+            "real" => Some(ValueType::Real),
+            "text" => Some(ValueType::Text),
+            "symbol" => Some(ValueType::Symbol),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ValueType::Int => "int",
+            ValueType::Real => "real",
+            ValueType::Text => "text",
+            ValueType::Symbol => "symbol",
+        }
+    }
+
+    // type of value, None for null
+    pub fn of(data_type: &DataType) -> Option<Self> {
+        match data_type {
+            DataType::Null => None,
+            DataType::Int(_) => Some(ValueType::Int),
+            DataType::Real(_) => Some(ValueType::Real),
+            DataType::Text(_) => Some(ValueType::Text),
+            DataType::Symbol(_) => Some(ValueType::Symbol),
+        }
+    }
+
+    // To check that variable of this type can be widened to other type: int -> real
+    pub fn is_widened_to(&self, other: ValueType) -> bool {
+        *self == other || (*self == ValueType::Int && other == ValueType::Real)
+    }
+}
+
+// Types of variables of table.
+// Variable declared without type (e.g. by null value) gets type of first value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    types: BTreeMap<String, Option<ValueType>>,
+}
+
+impl Schema {
+    // public function
+    pub fn new() -> Self {
+        Schema {
+            types: BTreeMap::new(),
+        }
+    }
+
+    // To infer schema from stored types of variables (e.g. of old snapshot),
+    // variable with several types which can't be widened to one stays untyped
+    pub fn infer<'a, I: Iterator<Item = (&'a String, Vec<ValueType>)>>(variables: I) -> Self {
+        let mut schema = Schema::new();
+        for (name, value_types) in variables {
+            let mut value_type: Option<ValueType> = None;
+            for other in value_types {
+                value_type = match value_type {
+                    None => Some(other),
+                    Some(current) if current.is_widened_to(other) => Some(other),
+                    Some(current) if other.is_widened_to(current) => Some(current),
+                    Some(_) => None,
+                };
+                if value_type.is_none() {
+                    break;
+                }
+            }
+            schema.types.insert(name.to_string(), value_type);
+        }
+        schema
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<ValueType> {
+        self.types.get(name).copied().flatten()
+    }
+
+    // To get declared variables with their types in alphabetical order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, Option<ValueType>)> {
+        self.types.iter().map(|(name, value_type)| (name, *value_type))
+    }
+
+    // To add variable or widen type of declared variable
+    pub fn declare(&mut self, name: &str, value_type: Option<ValueType>) -> Result<(), QueryError> {
+        let current = match self.types.get(name) {
+            Some(current) => *current,
+            None => {
+                self.types.insert(name.to_string(), value_type);
+                return Ok(());
+            }
+        };
+        match (current, value_type) {
+            (_, None) => Ok(()),
+            (Some(current), Some(value_type)) if !current.is_widened_to(value_type) => {
+                Err(QueryError::TypeMismatch(format!(
+                    "variable {} of type {} can't be changed to {}",
+                    name,
+                    current.get_name(),
+                    value_type.get_name()
+                )))
+            }
+            _ => {
+                self.types.insert(name.to_string(), value_type);
+                Ok(())
+            }
+        }
+    }
+
+    // To check value of declared variable, int value of real variable is converted to real.
+    // Returns UnknownVariable if variable isn't declared and TypeMismatch if type differs.
+    pub fn check(&self, name: &str, value: DataType) -> Result<DataType, QueryError> {
+        let declared = match self.types.get(name) {
+            Some(declared) => *declared,
+            None => return Err(QueryError::UnknownVariable(name.to_string())),
+        };
+        match (declared, ValueType::of(&value), value) {
+            (Some(ValueType::Real), Some(ValueType::Int), DataType::Int(value)) => Ok(DataType::Real(value as f64)),
+            (Some(declared), Some(value_type), value) if declared != value_type => {
+                Err(QueryError::TypeMismatch(format!(
+                    "variable {} is declared as {}, found {:?}",
+                    name,
+                    declared.get_name(),
+                    value
+                )))
+            }
+            (_, _, value) => Ok(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::schema::{Schema, ValueType};
+    use crate::error::query_error::QueryError;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_schema() {
+        let mut schema = Schema::new();
+        schema.declare("c", Some(ValueType::Int)).unwrap();
+        schema.declare("d", None).unwrap();
+
+        debug_assert_eq!(Ok(DataType::Int(1)), schema.check("c", DataType::Int(1)));
+        debug_assert_eq!(Ok(DataType::Null), schema.check("c", DataType::Null));
+        debug_assert!(matches!(schema.check("c", DataType::Real(1.5)), Err(QueryError::TypeMismatch(_))));
+        debug_assert_eq!(Ok(DataType::Text("a".to_string())), schema.check("d", DataType::Text("a".to_string())));
+        debug_assert_eq!(
+            Err(QueryError::UnknownVariable("e".to_string())),
+            schema.check("e", DataType::Int(1))
+        );
+
+        // widening
+        schema.declare("c", Some(ValueType::Real)).unwrap();
+        debug_assert_eq!(Ok(DataType::Real(1.0)), schema.check("c", DataType::Int(1)));
+        debug_assert!(matches!(schema.declare("c", Some(ValueType::Int)), Err(QueryError::TypeMismatch(_))));
+        debug_assert!(matches!(schema.declare("c", Some(ValueType::Text)), Err(QueryError::TypeMismatch(_))));
+
+        let names = ["a".to_string(), "b".to_string(), "c".to_string()];
+        let inferred = Schema::infer(
+            vec![
+                (&names[0], vec![ValueType::Int, ValueType::Real]),
+                (&names[1], vec![ValueType::Int, ValueType::Text]),
+                (&names[2], vec![]),
+            ]
+            .into_iter(),
+        );
+        debug_assert_eq!(Some(ValueType::Real), inferred.get_type("a"));
+        debug_assert_eq!(None, inferred.get_type("b"));
+        debug_assert!(inferred.contains("c"));
+    }
+}
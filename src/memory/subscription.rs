@@ -0,0 +1,131 @@
+use crate::memory::memory_machine::IntervalSet;
+use crate::memory::memory_table::MemoryTable;
+use crate::memory::predicate::Condition;
+use crate::memory::print_of_state::PrintOfState;
+use crate::memory::result_set::{ResultSet, Row};
+use qdb_ast::ast::types::DataType;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+
+// Row written into channel where condition of subscription is true:
+// values of variables at logic time of row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    subscription_id: u64,
+    channel_name: String,
+    logic_time: i64,
+    states: Vec<PrintOfState>,
+}
+
+impl Notification {
+    pub fn get_subscription_id(&self) -> u64 {
+        self.subscription_id
+    }
+
+    pub fn get_channel_name(&self) -> &str {
+        &self.channel_name
+    }
+
+    pub fn get_logic_time(&self) -> i64 {
+        self.logic_time
+    }
+
+    pub fn get_states(&self) -> &[PrintOfState] {
+        &self.states
+    }
+
+    // To get values as one row of result set, columns are names of variables
+    pub fn to_result_set(&self) -> ResultSet {
+        let mut result_set = ResultSet::new(self.states.iter().map(|state| state.get_name().to_string()).collect());
+        let values = self
+            .states
+            .iter()
+            .map(|state| state.get_values().last().cloned().unwrap_or(DataType::Null))
+            .collect();
+        result_set.push_row(Row::new(self.logic_time..=self.logic_time, values));
+        result_set
+    }
+}
+
+// Listener of writes of table, registered by onChange(channel)(condition).
+// Condition is bound to channel of subscription, as of onRead.
+#[derive(Debug)]
+pub struct Subscription {
+    id: u64,
+    channel_name: String,
+    condition: Condition,
+    sender: Sender<Notification>,
+}
+
+// Receiving end of subscriptions of one onChange query, one per channel.
+// Subscription is removed from table on next write after subscriber is dropped.
+#[derive(Debug)]
+pub struct Subscriber {
+    id: u64,
+    sender: Sender<Notification>,
+    receiver: Receiver<Notification>,
+}
+
+impl Subscriber {
+    // public function
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Subscriber {
+            id: NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed),
+            sender,
+            receiver,
+        }
+    }
+
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_receiver(&self) -> &Receiver<Notification> {
+        &self.receiver
+    }
+
+    // To create subscription to channel which sends to this subscriber
+    pub fn subscribe(&self, channel_name: &str, condition: Condition) -> Subscription {
+        Subscription {
+            id: self.id,
+            channel_name: channel_name.to_string(),
+            condition,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl Default for Subscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Subscription {
+    // public function
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    // To send values of row written at logic time if condition is true at it,
+    // row_table is state of table at this row (see MemoryTable::get_row_table).
+    // Returns false if subscriber is dropped.
+    pub fn notify(&self, row_table: &MemoryTable, logic_time: i64) -> bool {
+        let window = IntervalSet::from_range(0..=0);
+        // failed condition (e.g. by type of value) is not true
+        let states = match row_table.find_by_condition_in_window(&self.condition.to_predicate(), &window) {
+            Ok(states) if !states.is_empty() => states,
+            _ => return true,
+        };
+        let notification = Notification {
+            subscription_id: self.id,
+            channel_name: self.channel_name.to_string(),
+            logic_time,
+            states,
+        };
+        self.sender.send(notification).is_ok()
+    }
+}
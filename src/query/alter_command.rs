@@ -0,0 +1,132 @@
+use crate::memory::memory_channel::ChannelAccess;
+use crate::memory::schema::ValueType;
+use crate::error::query_error::QueryError;
+
+// Schema evolution of channels, it's resolved without query parser.
+// Declaration adds variable or widens type of declared variable (int -> real),
+// unknown channel is created with declared schema.
+// Example:
+// alter(my_node)(e:text, c:real)
+#[derive(Debug, PartialEq)]
+pub struct AlterCommand {
+    channel_names: Vec<String>,
+    declarations: Vec<(String, ValueType)>,
+}
+
+impl AlterCommand {
+    // hidden function
+    fn split_group(text: &str) -> Result<(&str, &str), QueryError> {
+        let text = text
+            .trim_start()
+            .strip_prefix('(')
+            .ok_or_else(|| QueryError::ParseError("alter requires '('".to_string()))?;
+        let end = text
+            .find(')')
+            .ok_or_else(|| QueryError::ParseError("alter requires ')'".to_string()))?;
+        Ok((&text[..end], &text[end + 1..]))
+    }
+
+    fn parse_name(name: &str) -> Result<String, QueryError> {
+        let name = name.trim();
+        let is_name = !name.is_empty()
+            && name.chars().all(|symbol| symbol.is_alphanumeric() || symbol == '_');
+        if !is_name {
+            return Err(QueryError::ParseError(format!("name expected, found '{}'", name)));
+        }
+        Ok(name.to_string())
+    }
+
+    fn parse_declaration(declaration: &str) -> Result<(String, ValueType), QueryError> {
+        let (name, type_name) = declaration.split_once(':').ok_or_else(|| {
+            QueryError::ParseError(format!("declaration name:type expected, found '{}'", declaration.trim()))
+        })?;
+        let value_type = ValueType::from_name(type_name.trim())
+            .ok_or_else(|| QueryError::ParseError(format!("unknown type: {}", type_name.trim())))?;
+        Ok((Self::parse_name(name)?, value_type))
+    }
+
+    // public function
+    // Returns None if line is not alter command
+    pub fn parse(line: &str) -> Option<Result<AlterCommand, QueryError>> {
+        let rest = line.trim().strip_prefix("alter")?;
+        if !rest.trim_start().starts_with('(') {
+            return None;
+        }
+
+        let parse = || -> Result<AlterCommand, QueryError> {
+            let (channel_names, rest) = Self::split_group(rest)?;
+            let (declarations, rest) = Self::split_group(rest)?;
+            if !rest.trim().is_empty() {
+                return Err(QueryError::ParseError(format!("unexpected text: {}", rest.trim())));
+            }
+            Ok(AlterCommand {
+                channel_names: channel_names
+                    .split(',')
+                    .map(Self::parse_name)
+                    .collect::<Result<_, _>>()?,
+                declarations: declarations
+                    .split(',')
+                    .map(Self::parse_declaration)
+                    .collect::<Result<_, _>>()?,
+            })
+        };
+        Some(parse())
+    }
+
+    pub fn get_channel_names(&self) -> &[String] {
+        &self.channel_names
+    }
+
+    pub fn get_declarations(&self) -> &[(String, ValueType)] {
+        &self.declarations
+    }
+
+    // Schemas of channels are changed only if declarations are valid for all channels
+    pub fn apply<C: ChannelAccess>(&self, channels: &mut C) -> Result<(), QueryError> {
+        for channel_name in self.channel_names.iter() {
+            if let Some(mem_table) = channels.get_table(channel_name) {
+                let mut schema = mem_table.get_schema().clone();
+                for (name, value_type) in self.declarations.iter() {
+                    schema.declare(name, Some(*value_type))?;
+                }
+            }
+        }
+
+        for channel_name in self.channel_names.iter() {
+            channels.get_or_create_table(channel_name)?.alter(&self.declarations)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::schema::ValueType;
+    use crate::query::alter_command::AlterCommand;
+    use crate::error::query_error::QueryError;
+
+    #[test]
+    fn test_alter_command_parse() {
+        let alter_command = AlterCommand::parse("alter(my_node, other_node)(e:text, c : real)")
+            .unwrap()
+            .unwrap();
+        debug_assert_eq!(
+            &["my_node".to_string(), "other_node".to_string()][..],
+            alter_command.get_channel_names()
+        );
+        debug_assert_eq!(
+            &[("e".to_string(), ValueType::Text), ("c".to_string(), ValueType::Real)][..],
+            alter_command.get_declarations()
+        );
+
+        debug_assert_eq!(None, AlterCommand::parse("onRead(my_node)()"));
+        for line in &[
+            "alter(my_node)",
+            "alter(my_node)(e)",
+            "alter(my_node)(e:date)",
+            "alter(my_node)(e:int) at 5",
+        ] {
+            debug_assert!(matches!(AlterCommand::parse(line), Some(Err(QueryError::ParseError(_)))));
+        }
+    }
+}
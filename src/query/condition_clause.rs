@@ -0,0 +1,354 @@
+use crate::memory::operator::OperatorRegistry;
+use crate::memory::predicate::{Condition, Operation};
+use crate::error::query_error::QueryError;
+use crate::query::query_scanner::{find_groups, split_top_level, QueryScanner, ROUND_BRACKETS};
+use qdb_ast::ast::types::{BinaryExpr, DataType};
+
+// operators which query parser turns into binary expressions,
+// longer operator is before its prefix, so it's matched first
+const PARSED_OPERATORS: [&str; 6] = ["==", "!=", ">=", ">", "<=", "<"];
+
+// Expressions of condition which query parser can't hold:
+// operators unknown to parser, disjunction by 'or' and negation by not(...).
+// They are cut from condition of first function of query
+// and evaluated as operations and conditions.
+// Brackets group conjunction inside disjunction or negation.
+// Example:
+// onRead(my_node)(c > 0, d in (1, 2, 3))
+// onRead(my_node)(d between 10 and 20, e like "ab%")
+// onRead(my_node)(c > 5 or d < 0, not(e == 1, f in (1, 2)))
+// onDelete(my_node)(e ~ "^a.c$" or (c > 0, d > 0))
+#[derive(Debug, Default)]
+pub struct ConditionClause {
+    operations: Vec<Operation>,
+    conditions: Vec<Condition>,
+}
+
+impl ConditionClause {
+    // hidden function
+    fn parse_value(token: &str) -> Result<DataType, QueryError> {
+        if token == "null" {
+            return Ok(DataType::Null);
+        }
+        if token.len() >= 2 && token.starts_with('"') && token.ends_with('"') {
+            let text = token[1..token.len() - 1].replace("\\\"", "\"").replace("\\\\", "\\");
+            return Ok(DataType::Text(text));
+        }
+        if let Ok(value) = token.parse::<i64>() {
+            return Ok(DataType::Int(value));
+        }
+        if let Ok(value) = token.parse::<f64>() {
+            return Ok(DataType::Real(value));
+        }
+        if Self::is_variable(token) {
+            return Ok(DataType::Symbol(token.to_string()));
+        }
+        Err(QueryError::ParseError(format!("value expected, found '{}'", token)))
+    }
+
+    // variable can be qualified by channel: my_node.c
+    fn is_variable(token: &str) -> bool {
+        match token.split_once('.') {
+            Some((channel_name, name)) => Self::is_name(channel_name) && Self::is_name(name),
+            None => Self::is_name(token),
+        }
+    }
+
+    // To split expression by whitespace, comparison operators are split out of tokens too:
+    // c>5 -> c, >, 5. Bracket groups and text are kept as one token.
+    fn tokenize(expression: &str) -> Vec<&str> {
+        let mut tokens: Vec<&str> = Vec::new();
+        for token in split_top_level(expression, ' ') {
+            if token.starts_with('(') {
+                tokens.push(token);
+                continue;
+            }
+            let mut start = 0;
+            for (position, _, depth) in QueryScanner::new(token, ROUND_BRACKETS) {
+                if position < start || depth > 0 {
+                    continue;
+                }
+                let rest = &token[position..];
+                if let Some(operator) = PARSED_OPERATORS.iter().find(|operator| rest.starts_with(*operator)) {
+                    if start < position {
+                        tokens.push(&token[start..position]);
+                    }
+                    tokens.push(operator);
+                    start = position + operator.len();
+                }
+            }
+            if start < token.len() {
+                tokens.push(&token[start..]);
+            }
+        }
+        tokens
+    }
+
+    // content of bracket group: (a, b) -> a, b
+    fn get_group(token: &str) -> Option<&str> {
+        token.strip_prefix('(').and_then(|token| token.strip_suffix(')'))
+    }
+
+    // operands are list in brackets: (a, b, c)
+    // or values joined by 'and': a and b
+    fn parse_operands(tokens: &[&str]) -> Result<Vec<DataType>, QueryError> {
+        if let [token] = tokens {
+            if let Some(list) = Self::get_group(token) {
+                return split_top_level(list, ',')
+                    .into_iter()
+                    .map(Self::parse_value)
+                    .collect();
+            }
+        }
+
+        let mut operands: Vec<DataType> = Vec::new();
+        for (position, token) in tokens.iter().enumerate() {
+            if position % 2 == 0 {
+                operands.push(Self::parse_value(token)?);
+            } else if *token != "and" {
+                return Err(QueryError::ParseError(format!("'and' expected, found '{}'", token)));
+            }
+        }
+        if operands.is_empty() || tokens.len() != 2 * operands.len() - 1 {
+            return Err(QueryError::ParseError("operand expected".to_string()));
+        }
+        Ok(operands)
+    }
+
+    // expression is cut when its operator is registered, but not parsed by query parser
+    fn parse_operation(tokens: &[&str]) -> Result<Option<Operation>, QueryError> {
+        let (name, operator) = match tokens {
+            [name, operator, ..] => (*name, *operator),
+            _ => return Ok(None),
+        };
+        let is_operation = Self::is_variable(name)
+            && !PARSED_OPERATORS.contains(&operator)
+            && OperatorRegistry::global().contains(operator);
+        if !is_operation {
+            return Ok(None);
+        }
+        let operands = Self::parse_operands(&tokens[2..])?;
+        Ok(Some(Operation::new(name, operator, operands)))
+    }
+
+    // condition is cut when expression has 'or' or starts with not(...)
+    fn is_condition(tokens: &[&str]) -> bool {
+        tokens.contains(&"or") || matches!(tokens, ["not", group] if group.starts_with('('))
+    }
+
+    // a or b or c, where operand is expression, not(...) or bracket group
+    fn parse_condition(tokens: &[&str]) -> Result<Condition, QueryError> {
+        let mut conditions = tokens
+            .split(|token| *token == "or")
+            .map(Self::parse_term)
+            .collect::<Result<Vec<Condition>, QueryError>>()?;
+        if conditions.len() == 1 {
+            return Ok(conditions.remove(0));
+        }
+        Ok(Condition::Or(conditions))
+    }
+
+    fn parse_term(tokens: &[&str]) -> Result<Condition, QueryError> {
+        match tokens {
+            ["not", token] => match Self::get_group(token) {
+                Some(group) => Ok(Condition::Not(Box::new(Self::parse_group(group)?))),
+                None => Err(QueryError::ParseError(format!("not(...) expected, found 'not {}'", token))),
+            },
+            [token] if Self::get_group(token).is_some() => Self::parse_group(&token[1..token.len() - 1]),
+            [left, operator, right] if PARSED_OPERATORS.contains(operator) => Ok(Condition::Expr(BinaryExpr::new(
+                Self::parse_value(left)?,
+                Self::parse_value(right)?,
+                operator.to_string(),
+            ))),
+            _ => match Self::parse_operation(tokens)? {
+                Some(operation) => Ok(Condition::Operation(operation)),
+                None => Err(QueryError::ParseError(format!(
+                    "expression expected, found '{}'",
+                    tokens.join(" ")
+                ))),
+            },
+        }
+    }
+
+    // expressions of group separated by ',' are conjunction
+    fn parse_group(group: &str) -> Result<Condition, QueryError> {
+        let mut conditions = split_top_level(group, ',')
+            .into_iter()
+            .map(|expression| Self::parse_condition(&Self::tokenize(expression)))
+            .collect::<Result<Vec<Condition>, QueryError>>()?;
+        if conditions.len() == 1 {
+            return Ok(conditions.remove(0));
+        }
+        Ok(Condition::And(conditions))
+    }
+
+    // public function
+    // To split query line to query for parser and operations and conditions of its condition
+    pub fn split(line: &str) -> Result<(String, ConditionClause), QueryError> {
+        let mut condition_clause = ConditionClause::default();
+        let function_name = line.split('(').next().unwrap_or("").trim();
+        let groups = find_groups(line);
+        let (start, end) = match (function_name, groups.get(1)) {
+            ("onRead", Some(group)) | ("onUpdate", Some(group)) | ("onDelete", Some(group)) => *group,
+            _ => return Ok((line.to_string(), condition_clause)),
+        };
+
+        let mut expressions: Vec<&str> = Vec::new();
+        for expression in split_top_level(&line[start..end], ',') {
+            let tokens = Self::tokenize(expression);
+            if Self::is_condition(&tokens) {
+                condition_clause.conditions.push(Self::parse_condition(&tokens)?);
+                continue;
+            }
+            match Self::parse_operation(&tokens)? {
+                Some(operation) => condition_clause.operations.push(operation),
+                None => expressions.push(expression),
+            }
+        }
+        if condition_clause.operations.is_empty() && condition_clause.conditions.is_empty() {
+            return Ok((line.to_string(), condition_clause));
+        }
+        let line = format!("{}{}{}", &line[..start], expressions.join(", "), &line[end..]);
+        Ok((line, condition_clause))
+    }
+
+    // To parse condition group of query which isn't given to parser (e.g. of onChange),
+    // every expression of group is condition
+    pub fn parse(group: &str) -> Result<ConditionClause, QueryError> {
+        let mut condition_clause = ConditionClause::default();
+        if group.trim().is_empty() {
+            return Ok(condition_clause);
+        }
+        for expression in split_top_level(group, ',') {
+            let condition = Self::parse_condition(&Self::tokenize(expression))?;
+            condition_clause.conditions.push(condition);
+        }
+        Ok(condition_clause)
+    }
+
+    pub fn is_name(token: &str) -> bool {
+        matches!(token.chars().next(), Some(symbol) if symbol.is_alphabetic() || symbol == '_')
+            && token.chars().all(|symbol| symbol.is_alphanumeric() || symbol == '_')
+    }
+
+    pub fn get_operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn get_conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::predicate::{Condition, Operation};
+    use crate::query::condition_clause::ConditionClause;
+    use crate::error::query_error::QueryError;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_condition_clause_split() {
+        let (line, condition_clause) = ConditionClause::split("onRead(my_node)(c > 0)").unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert!(condition_clause.get_operations().is_empty());
+
+        let (line, condition_clause) = ConditionClause::split(
+            "onRead(my_node)(c > 0, d in (1, 2.5, \"a, b\"), e between -1 and 1, my_node.f like \"(ab%\")",
+        )
+        .unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert_eq!(
+            &[
+                Operation::new(
+                    "d",
+                    "in",
+                    vec![DataType::Int(1), DataType::Real(2.5), DataType::Text("a, b".to_string())]
+                ),
+                Operation::new("e", "between", vec![DataType::Int(-1), DataType::Int(1)]),
+                Operation::new("my_node.f", "like", vec![DataType::Text("(ab%".to_string())]),
+            ][..],
+            condition_clause.get_operations()
+        );
+
+        let (line, condition_clause) =
+            ConditionClause::split("onUpdate(my_node)(d ~ \"^a\\\"\")(c = 1)").unwrap();
+        debug_assert_eq!("onUpdate(my_node)()(c = 1)", line);
+        debug_assert_eq!(
+            &[Operation::new("d", "~", vec![DataType::Text("^a\"".to_string())])][..],
+            condition_clause.get_operations()
+        );
+
+        let (line, _) = ConditionClause::split("onCreate(my_node)(c:int = 1)").unwrap();
+        debug_assert_eq!("onCreate(my_node)(c:int = 1)", line);
+
+        for line in &[
+            "onRead(my_node)(d in ())",
+            "onRead(my_node)(d between 1 or 2)",
+            "onRead(my_node)(d between 1 and)",
+            "onRead(my_node)(d like ?)",
+        ] {
+            debug_assert!(matches!(ConditionClause::split(line), Err(QueryError::ParseError(_))));
+        }
+    }
+
+    #[test]
+    fn test_condition_clause_split_conditions() {
+        let (line, condition_clause) = ConditionClause::split(
+            "onRead(my_node)(a == 1, c > 5 or d < 0, not(e in (1, 2), my_node.f == g))",
+        )
+        .unwrap();
+        debug_assert_eq!("onRead(my_node)(a == 1)", line);
+        debug_assert!(condition_clause.get_operations().is_empty());
+        match condition_clause.get_conditions() {
+            [Condition::Or(or), Condition::Not(not)] => {
+                debug_assert!(matches!(or.as_slice(), [Condition::Expr(_), Condition::Expr(_)]));
+                let and = match not.as_ref() {
+                    Condition::And(and) => and.as_slice(),
+                    condition => panic!("conjunction expected, found {:?}", condition),
+                };
+                debug_assert!(matches!(and, [Condition::Operation(_), Condition::Expr(_)]));
+                debug_assert_eq!(vec!["e", "my_node.f", "g"], condition_clause.get_conditions()[1].get_names());
+            }
+            conditions => panic!("or and not expected, found {:?}", conditions),
+        }
+
+        // operators are split out of tokens without whitespace, longest first
+        let (line, condition_clause) =
+            ConditionClause::split("onRead(my_node)(c>=5 or d<-1, not(e!=\"a<b\"))").unwrap();
+        assert_eq!("onRead(my_node)()", line);
+        match condition_clause.get_conditions() {
+            [Condition::Or(or), Condition::Not(not)] => {
+                let operators: Vec<&String> = or
+                    .iter()
+                    .chain(std::iter::once(not.as_ref()))
+                    .map(|condition| match condition {
+                        Condition::Expr(binary_expr) => binary_expr.get().2,
+                        condition => panic!("expression expected, found {:?}", condition),
+                    })
+                    .collect();
+                assert_eq!(vec![">=", "<", "!="], operators);
+                assert_eq!(vec!["c", "d"], condition_clause.get_conditions()[0].get_names());
+            }
+            conditions => panic!("or and not expected, found {:?}", conditions),
+        }
+
+        let (line, condition_clause) =
+            ConditionClause::split("onDelete(my_node)(e like \"a%\" or (c > 0, not(d > 0)))").unwrap();
+        debug_assert_eq!("onDelete(my_node)()", line);
+        debug_assert!(matches!(
+            condition_clause.get_conditions(),
+            [Condition::Or(or)] if matches!(or.as_slice(), [Condition::Operation(_), Condition::And(_)])
+        ));
+
+        for line in &[
+            "onRead(my_node)(c > 5 or)",
+            "onRead(my_node)(c > 5 or d)",
+            "onRead(my_node)(not(c > 5 or))",
+            "onRead(my_node)(c > 5 or not d > 0)",
+            "onRead(my_node)(c >> 5 or d < 0)",
+        ] {
+            debug_assert!(matches!(ConditionClause::split(line), Err(QueryError::ParseError(_))));
+        }
+    }
+}
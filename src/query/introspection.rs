@@ -0,0 +1,159 @@
+use crate::memory::memory_channel::ChannelAccess;
+use crate::memory::memory_table::MemoryTable;
+use crate::memory::result_set::{ResultSet, Row};
+use crate::error::query_error::QueryError;
+use qdb_ast::ast::types::DataType;
+
+// Queries about content of database, they are resolved without query parser.
+// Logic time of row is its number.
+// Example:
+// channels()          - channels with logic time, count of variables and memory usage
+// describe(my_node)   - variables of channel with observed types, count of distinct values,
+//                       count of continuous ranges of values and memory usage
+pub struct Introspection;
+
+impl Introspection {
+    // hidden function
+    fn split_call(line: &str) -> Option<(&str, &str)> {
+        let line = line.trim().strip_suffix(')')?;
+        let (name, argument) = line.split_once('(')?;
+        Some((name.trim(), argument.trim()))
+    }
+
+    fn to_int(value: usize) -> DataType {
+        DataType::Int(value as i64)
+    }
+
+    fn describe_channels<C: ChannelAccess>(channels: &C) -> ResultSet {
+        let mut result_set = ResultSet::new(
+            ["channel", "logic_time", "variables", "memory_bytes"]
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+        );
+        for (number, (channel_name, mem_table)) in channels.get_tables().into_iter().enumerate() {
+            let number = number as i64;
+            result_set.push_row(Row::new(
+                number..=number,
+                vec![
+                    DataType::Symbol(channel_name.to_string()),
+                    DataType::Int(mem_table.get_logic_time()),
+                    Self::to_int(mem_table.get_var_names().len()),
+                    Self::to_int(mem_table.get_memory_usage()),
+                ],
+            ));
+        }
+        result_set
+    }
+
+    fn describe_channel(mem_table: &MemoryTable) -> ResultSet {
+        let mut result_set = ResultSet::new(
+            ["variable", "types", "distinct_values", "ranges", "memory_bytes"]
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+        );
+        for (number, name) in mem_table.get_var_names().into_iter().enumerate() {
+            let mem_machine = match mem_table.get_var(name) {
+                Some(mem_machine) => mem_machine,
+                None => continue,
+            };
+            let number = number as i64;
+            result_set.push_row(Row::new(
+                number..=number,
+                vec![
+                    DataType::Symbol(name.to_string()),
+                    DataType::Text(mem_machine.get_type_names().join(",")),
+                    Self::to_int(mem_machine.get_distinct_count()),
+                    Self::to_int(mem_machine.get_ranges_count()),
+                    Self::to_int(mem_machine.get_memory_usage()),
+                ],
+            ));
+        }
+        result_set
+    }
+
+    // public function
+    // Returns None if line is not introspection query
+    pub fn resolve<C: ChannelAccess>(channels: &C, line: &str) -> Option<Result<ResultSet, QueryError>> {
+        let result = match Self::split_call(line)? {
+            ("channels", "") => Ok(Self::describe_channels(channels)),
+            ("channels", argument) => Err(QueryError::ParseError(format!(
+                "channels takes no arguments, found {}",
+                argument
+            ))),
+            ("describe", channel_name) => channels
+                .get_table(&channel_name.to_string())
+                .map(Self::describe_channel)
+                .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string())),
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_channel::MemoryChannel;
+    use crate::memory::result_set::Row;
+    use crate::query::introspection::Introspection;
+    use crate::error::query_error::QueryError;
+    use crate::query::query_resolver::QueryResolver;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_introspection_resolve() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "alter(my_node)(c:real)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:real = 2.5)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+
+        let channels = Introspection::resolve(&a, "channels()").unwrap().unwrap();
+        debug_assert_eq!(
+            vec!["channel", "logic_time", "variables", "memory_bytes"],
+            channels.get_columns().to_vec()
+        );
+        debug_assert_eq!(1, channels.get_rows().len());
+        debug_assert_eq!(
+            &[DataType::Symbol("my_node".to_string()), DataType::Int(4), DataType::Int(2)][..],
+            &channels.get_rows()[0].get_values()[..3]
+        );
+
+        let variables = Introspection::resolve(&a, "describe( my_node )").unwrap().unwrap();
+        let c_memory_usage = variables.get_rows()[0].get_values()[4].clone();
+        debug_assert_eq!(
+            &[
+                Row::new(
+                    0..=0,
+                    vec![
+                        DataType::Symbol("c".to_string()),
+                        DataType::Text("int,real".to_string()),
+                        DataType::Int(3),
+                        DataType::Int(3),
+                        c_memory_usage.clone(),
+                    ]
+                ),
+                Row::new(
+                    1..=1,
+                    vec![
+                        DataType::Symbol("d".to_string()),
+                        DataType::Text("int".to_string()),
+                        DataType::Int(1),
+                        DataType::Int(1),
+                        variables.get_rows()[1].get_values()[4].clone(),
+                    ]
+                ),
+            ][..],
+            variables.get_rows()
+        );
+        debug_assert!(matches!(c_memory_usage, DataType::Int(bytes) if bytes > 0));
+
+        debug_assert_eq!(
+            Some(Err(QueryError::UnknownChannel("other_node".to_string()))),
+            Introspection::resolve(&a, "describe(other_node)")
+        );
+        debug_assert!(Introspection::resolve(&a, "onRead(my_node)()").is_none());
+    }
+}
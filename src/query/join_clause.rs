@@ -0,0 +1,391 @@
+use crate::memory::result_set::{ResultSet, Row};
+use crate::query::condition_clause::ConditionClause;
+use crate::error::query_error::QueryError;
+use crate::query::query_scanner::{find_groups, split_top_level};
+use qdb_ast::ast::types::DataType;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+// operators of join expressions, longer ones are matched first
+const JOIN_OPERATORS: [&str; 6] = ["==", "!=", ">=", "<=", ">", "<"];
+
+// Comparison of variables of two channels: left_channel.left_name operator right_channel.right_name
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinExpr {
+    left_channel: String,
+    left_name: String,
+    operator: String,
+    right_channel: String,
+    right_name: String,
+}
+
+impl JoinExpr {
+    // public function
+    pub fn new(left: (&str, &str), operator: &str, right: (&str, &str)) -> Self {
+        JoinExpr {
+            left_channel: left.0.to_string(),
+            left_name: left.1.to_string(),
+            operator: operator.to_string(),
+            right_channel: right.0.to_string(),
+            right_name: right.1.to_string(),
+        }
+    }
+
+    pub fn get_left(&self) -> (&str, &str) {
+        (&self.left_channel, &self.left_name)
+    }
+
+    pub fn get_right(&self) -> (&str, &str) {
+        (&self.right_channel, &self.right_name)
+    }
+
+    // Null matches nothing, values which can't be compared don't match
+    pub fn is_match(&self, left: &DataType, right: &DataType) -> bool {
+        if *left == DataType::Null || *right == DataType::Null {
+            return false;
+        }
+        let ordering = match left.compare_with(right) {
+            Some(ordering) => ordering,
+            None => return false,
+        };
+        match self.operator.as_str() {
+            "==" => ordering == Ordering::Equal,
+            "!=" => ordering != Ordering::Equal,
+            ">=" => ordering != Ordering::Less,
+            ">" => ordering == Ordering::Greater,
+            "<=" => ordering != Ordering::Greater,
+            _ => ordering == Ordering::Less,
+        }
+    }
+}
+
+// Rows of channel taking part in join
+#[derive(Debug)]
+pub struct JoinInput<'a> {
+    pub channel_name: &'a str,
+    pub result_set: ResultSet,
+}
+
+// Expressions of onRead condition which compare variables of two different channels.
+// They are cut from condition before query parser and resolved by join of rows of
+// both channels: every row of left channel is matched with every row of right one
+// where all expressions are true. Channels have own clocks, so rows aren't aligned
+// by logic time, row of result is at logic times of its left row.
+// Rows of right channel are sorted by value of first expression, so rows which match
+// row of left channel are found by binary search instead of scan of all pairs.
+// Other expressions of condition filter rows of channels which have their variables.
+// Columns of result are qualified by channel: orders.user_id, users.id.
+// Example:
+// onRead(orders, users)(orders.user_id == users.id)
+// onRead(orders, users)(orders.user_id == users.id, amount > 10)[orders.amount, users.name]
+#[derive(Debug, Default, PartialEq)]
+pub struct JoinClause {
+    join_exprs: Vec<JoinExpr>,
+}
+
+impl JoinClause {
+    // hidden function
+    // To split channel.var, channel must be one of listed channels
+    fn parse_qualified<'a>(token: &'a str, channel_names: &[&str]) -> Option<(&'a str, &'a str)> {
+        let (channel_name, name) = token.trim().split_once('.')?;
+        let is_qualified = channel_names.contains(&channel_name)
+            && ConditionClause::is_name(channel_name)
+            && ConditionClause::is_name(name);
+        if is_qualified {
+            Some((channel_name, name))
+        } else {
+            None
+        }
+    }
+
+    // expression is join expression if it compares variables of two listed channels
+    fn parse_join_expr(expression: &str, channel_names: &[&str]) -> Option<JoinExpr> {
+        let (left, operator, right) = JOIN_OPERATORS
+            .iter()
+            .find_map(|operator| expression.split_once(operator).map(|(left, right)| (left, *operator, right)))?;
+        let left = Self::parse_qualified(left, channel_names)?;
+        let right = Self::parse_qualified(right, channel_names)?;
+        if left.0 == right.0 {
+            return None;
+        }
+        Some(JoinExpr::new(left, operator, right))
+    }
+
+    // values of one group compare with each other: numbers, texts, others
+    fn get_group(value: &DataType) -> u8 {
+        match value {
+            DataType::Int(_) | DataType::Real(_) => 0,
+            DataType::Text(_) => 1,
+            _ => 2,
+        }
+    }
+
+    // To get positions of sorted values x where value operator x is true,
+    // where values from lower to upper are equal to value
+    fn get_candidate_ranges(operator: &str, lower: usize, upper: usize, len: usize) -> [Range<usize>; 2] {
+        match operator {
+            "==" => [lower..upper, 0..0],
+            "!=" => [0..lower, upper..len],
+            ">" => [0..lower, 0..0],
+            ">=" => [0..upper, 0..0],
+            "<" => [upper..len, 0..0],
+            _ => [lower..len, 0..0],
+        }
+    }
+
+    fn get_value<'a>(input: &'a JoinInput, row: &'a Row, name: &str) -> &'a DataType {
+        input
+            .result_set
+            .get_column_index(name)
+            .and_then(|index| row.get_value(index))
+            .unwrap_or(&DataType::Null)
+    }
+
+    // public function
+    // To split query line to query for parser and join expressions of onRead
+    pub fn split(line: &str) -> Result<(String, JoinClause), QueryError> {
+        let mut join_clause = JoinClause::default();
+        let function_name = line.split('(').next().unwrap_or("").trim();
+        let groups = find_groups(line);
+        let (channels, (start, end)) = match (function_name, groups.first(), groups.get(1)) {
+            ("onRead", Some(channels), Some(group)) => (*channels, *group),
+            _ => return Ok((line.to_string(), join_clause)),
+        };
+        let channel_names: Vec<&str> = split_top_level(&line[channels.0..channels.1], ',');
+
+        let mut expressions: Vec<&str> = Vec::new();
+        for expression in split_top_level(&line[start..end], ',') {
+            match Self::parse_join_expr(expression, &channel_names) {
+                Some(join_expr) => join_clause.join_exprs.push(join_expr),
+                None => expressions.push(expression),
+            }
+        }
+        if join_clause.join_exprs.is_empty() {
+            return Ok((line.to_string(), join_clause));
+        }
+
+        // join expressions must be between one pair of channels,
+        // left channel is the one listed first
+        let (left_channel, right_channel) = {
+            let (left, _) = join_clause.join_exprs[0].get_left();
+            let (right, _) = join_clause.join_exprs[0].get_right();
+            let position = |name: &str| channel_names.iter().position(|channel_name| *channel_name == name);
+            if position(left) <= position(right) {
+                (left.to_string(), right.to_string())
+            } else {
+                (right.to_string(), left.to_string())
+            }
+        };
+        for join_expr in join_clause.join_exprs.iter_mut() {
+            if join_expr.left_channel == right_channel && join_expr.right_channel == left_channel {
+                std::mem::swap(&mut join_expr.left_channel, &mut join_expr.right_channel);
+                std::mem::swap(&mut join_expr.left_name, &mut join_expr.right_name);
+                join_expr.operator = match join_expr.operator.as_str() {
+                    ">=" => "<=",
+                    "<=" => ">=",
+                    ">" => "<",
+                    "<" => ">",
+                    operator => operator,
+                }
+                .to_string();
+            }
+            if join_expr.left_channel != left_channel || join_expr.right_channel != right_channel {
+                return Err(QueryError::ParseError(
+                    "join expressions must compare one pair of channels".to_string(),
+                ));
+            }
+        }
+        let line = format!("{}{}{}", &line[..start], expressions.join(", "), &line[end..]);
+        Ok((line, join_clause))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.join_exprs.is_empty()
+    }
+
+    pub fn get_join_exprs(&self) -> &[JoinExpr] {
+        &self.join_exprs
+    }
+
+    // names of joined channels: (left, right)
+    pub fn get_channel_names(&self) -> Option<(&str, &str)> {
+        let join_expr = self.join_exprs.first()?;
+        Some((join_expr.get_left().0, join_expr.get_right().0))
+    }
+
+    // To join rows of left and right channels.
+    // Rows of result are in order of logic time of left rows, then of right rows.
+    pub fn join(&self, left: &JoinInput, right: &JoinInput) -> ResultSet {
+        let mut columns: Vec<String> = Vec::new();
+        for input in &[left, right] {
+            for column in input.result_set.get_columns() {
+                columns.push(format!("{}.{}", input.channel_name, column));
+            }
+        }
+        let mut result_set = ResultSet::new(columns);
+        let first = match self.join_exprs.first() {
+            Some(join_expr) => join_expr,
+            None => return result_set,
+        };
+
+        // values of first expression of right rows, Null and values which can't be compared
+        // (e.g. NaN) match nothing
+        let right_rows = right.result_set.get_rows();
+        let mut sorted: Vec<(u8, &DataType, usize)> = right_rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (Self::get_value(right, row, &first.right_name), index))
+            .filter(|(value, _)| **value != DataType::Null && value.compare_with(value).is_some())
+            .map(|(value, index)| (Self::get_group(value), value, index))
+            .collect();
+        sorted.sort_by(|(left_group, left_value, _), (right_group, right_value, _)| {
+            left_group
+                .cmp(right_group)
+                .then_with(|| left_value.compare_with(right_value).unwrap_or(Ordering::Equal))
+        });
+
+        for left_row in left.result_set.get_rows() {
+            let value = Self::get_value(left, left_row, &first.left_name);
+            if *value == DataType::Null || value.compare_with(value).is_none() {
+                continue;
+            }
+            let group = Self::get_group(value);
+            let start = sorted.partition_point(|(other_group, _, _)| *other_group < group);
+            let end = sorted.partition_point(|(other_group, _, _)| *other_group <= group);
+            let values = &sorted[start..end];
+            let compare = |other: &DataType| other.compare_with(value).unwrap_or(Ordering::Equal);
+            let lower = values.partition_point(|(_, other, _)| compare(other) == Ordering::Less);
+            let upper = values.partition_point(|(_, other, _)| compare(other) != Ordering::Greater);
+
+            let mut indexes: Vec<usize> = Self::get_candidate_ranges(&first.operator, lower, upper, values.len())
+                .iter()
+                .flat_map(|range| values[range.clone()].iter().map(|(_, _, index)| *index))
+                .collect();
+            indexes.sort_unstable();
+            for index in indexes {
+                let right_row = &right_rows[index];
+                let is_match = self.join_exprs.iter().all(|join_expr| {
+                    join_expr.is_match(
+                        Self::get_value(left, left_row, &join_expr.left_name),
+                        Self::get_value(right, right_row, &join_expr.right_name),
+                    )
+                });
+                if is_match {
+                    let mut values = left_row.get_values().to_vec();
+                    values.extend_from_slice(right_row.get_values());
+                    result_set.push_row(Row::new(left_row.get_logic_times().clone(), values));
+                }
+            }
+        }
+        result_set
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::query::join_clause::{JoinClause, JoinExpr, JoinInput};
+    use crate::error::query_error::QueryError;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_join_clause_split() {
+        let (line, join_clause) = JoinClause::split("onRead(my_node)(c > 0)").unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert!(join_clause.is_empty());
+
+        let (line, join_clause) =
+            JoinClause::split("onRead(orders, users)(orders.user_id == users.id, amount > 10, users.age<orders.age)")
+                .unwrap();
+        debug_assert_eq!("onRead(orders, users)(amount > 10)", line);
+        debug_assert_eq!(
+            &[
+                JoinExpr::new(("orders", "user_id"), "==", ("users", "id")),
+                JoinExpr::new(("orders", "age"), ">", ("users", "age")),
+            ][..],
+            join_clause.get_join_exprs()
+        );
+        debug_assert_eq!(Some(("orders", "users")), join_clause.get_channel_names());
+
+        // left channel is listed first
+        let (_, join_clause) = JoinClause::split("onRead(orders, users)(users.id == orders.user_id)").unwrap();
+        debug_assert_eq!(
+            &[JoinExpr::new(("orders", "user_id"), "==", ("users", "id"))][..],
+            join_clause.get_join_exprs()
+        );
+
+        // qualified by not listed channel, or by one channel is not join
+        let (line, join_clause) = JoinClause::split("onRead(orders, users)(orders.a == items.b)").unwrap();
+        debug_assert_eq!("onRead(orders, users)(orders.a == items.b)", line);
+        debug_assert!(join_clause.is_empty());
+
+        debug_assert!(matches!(
+            JoinClause::split("onRead(a, b, c)(a.x == b.x, a.x == c.x)"),
+            Err(QueryError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_join_clause_join() {
+        let (_, join_clause) = JoinClause::split("onRead(orders, users)(orders.user_id == users.id)").unwrap();
+
+        let mut orders = ResultSet::new(vec!["user_id".to_string()]);
+        orders.push_row(Row::new(0..=0, vec![DataType::Int(1)]));
+        orders.push_row(Row::new(1..=1, vec![DataType::Int(2)]));
+        orders.push_row(Row::new(2..=3, vec![DataType::Null]));
+        orders.push_row(Row::new(4..=4, vec![DataType::Real(2.0)]));
+        orders.push_row(Row::new(5..=5, vec![DataType::Int(1)]));
+        let mut users = ResultSet::new(vec!["id".to_string(), "name".to_string()]);
+        users.push_row(Row::new(0..=0, vec![DataType::Int(2), DataType::Text("b".to_string())]));
+        users.push_row(Row::new(1..=1, vec![DataType::Text("x".to_string()), DataType::Text("c".to_string())]));
+        users.push_row(Row::new(2..=2, vec![DataType::Int(1), DataType::Text("a".to_string())]));
+
+        let left = JoinInput {
+            channel_name: "orders",
+            result_set: orders,
+        };
+        let right = JoinInput {
+            channel_name: "users",
+            result_set: users,
+        };
+        let result_set = join_clause.join(&left, &right);
+        assert_eq!(
+            &["orders.user_id".to_string(), "users.id".to_string(), "users.name".to_string()],
+            result_set.get_columns()
+        );
+        // rows match by values whatever their logic times are
+        let a = || DataType::Text("a".to_string());
+        let b = || DataType::Text("b".to_string());
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(1), a()]),
+                Row::new(1..=1, vec![DataType::Int(2), DataType::Int(2), b()]),
+                Row::new(4..=4, vec![DataType::Real(2.0), DataType::Int(2), b()]),
+                Row::new(5..=5, vec![DataType::Int(1), DataType::Int(1), a()]),
+            ],
+            result_set.get_rows()
+        );
+
+        // rows of right channel in order of logic time for each left row
+        let (_, join_clause) = JoinClause::split("onRead(orders, users)(orders.user_id < users.id)").unwrap();
+        let result_set = join_clause.join(&left, &right);
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(2), b()]),
+                Row::new(5..=5, vec![DataType::Int(1), DataType::Int(2), b()]),
+            ],
+            result_set.get_rows()
+        );
+        let (_, join_clause) = JoinClause::split("onRead(orders, users)(orders.user_id != users.id)").unwrap();
+        let result_set = join_clause.join(&left, &right);
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(2), b()]),
+                Row::new(1..=1, vec![DataType::Int(2), DataType::Int(1), a()]),
+                Row::new(4..=4, vec![DataType::Real(2.0), DataType::Int(1), a()]),
+                Row::new(5..=5, vec![DataType::Int(1), DataType::Int(2), b()]),
+            ],
+            result_set.get_rows()
+        );
+    }
+}
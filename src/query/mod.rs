@@ -1 +1,9 @@
+pub mod alter_command;
+pub mod condition_clause;
+pub mod introspection;
+pub mod join_clause;
 pub mod query_resolver;
+pub mod query_scanner;
+pub mod read_clause;
+pub mod retention_command;
+pub mod transaction;
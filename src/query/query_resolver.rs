@@ -1,97 +1,1438 @@
-use crate::memory::memory_channel::MemoryChannel;
-use crate::memory::memory_table;
+use crate::memory::aggregate::Aggregate;
+use crate::memory::memory_channel::{ChannelAccess, MemoryChannel};
+use crate::memory::memory_machine::IntervalSet;
 use crate::memory::memory_table::MemoryTable;
-use qdb_ast::ast::types::{DataType, FuncType, UnaryFuncExpr};
+use crate::memory::predicate::{Condition, Operation, Predicate};
+use crate::memory::result_set::ResultSet;
+use crate::memory::schema::ValueType;
+use crate::memory::subscription::Subscriber;
+use crate::query::alter_command::AlterCommand;
+use crate::query::condition_clause::ConditionClause;
+use crate::query::introspection::Introspection;
+use crate::query::join_clause::{JoinClause, JoinInput};
+use crate::query::query_scanner::{find_groups, split_top_level};
+use crate::error::query_error::QueryError;
+use crate::query::read_clause::ReadClause;
+use crate::query::retention_command::RetentionCommand;
+use crate::query::transaction::Transaction;
+use crate::storage::write_ahead_log::{WalRecord, WriteAheadLog};
+use qdb_ast::ast::types::{BinaryExpr, DataType, DataVar, FuncType, UnaryFuncExpr};
 use qdb_ast::parser::states::DefaultParser;
-use crate::memory::print_of_state::PrintOfState;
-use std::borrow::BorrowMut;
+use std::collections::{BTreeMap, BTreeSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 
 pub struct QueryResolver;
 
+// Receiver of log record of mutating query, it's called before query is applied
+pub type AppendLog<'a> = dyn FnMut(&WalRecord) -> Result<(), QueryError> + 'a;
+
+// Expressions, operations and conditions (or, not) of condition bound to one channel
+type ChannelCondition<'a> = (Vec<&'a BinaryExpr>, Vec<&'a Operation>, Vec<&'a Condition>);
+
 pub enum QueryResponse {
-    PrintOfStates(Vec<PrintOfState>),
+    ResultSet(ResultSet),
+    // receiver of rows of onChange
+    Subscription(Subscriber),
     None
 }
 
 impl QueryResolver {
-    pub fn resolve(mem_channel: &mut MemoryChannel, line: String) -> QueryResponse {
-        let ast = DefaultParser::parse_from_string(line);
-        for unary_func_expr in ast {
+    // hidden function
+    fn parse(line: String) -> Result<Vec<UnaryFuncExpr>, QueryError> {
+        // parser panics on malformed input, so it's caught here
+        // to keep the process alive
+        let maybe_ast = catch_unwind(AssertUnwindSafe(|| DefaultParser::parse_from_string(line)));
+        match maybe_ast {
+            Ok(ast) => Ok(ast),
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<String>() {
+                    message.to_string()
+                } else if let Some(message) = payload.downcast_ref::<&str>() {
+                    message.to_string()
+                } else {
+                    "malformed query".to_string()
+                };
+                Err(QueryError::ParseError(message))
+            }
+        }
+    }
+
+    fn get_channel_name(channel_data_type_name: &DataType) -> Result<&String, QueryError> {
+        channel_data_type_name.symbol_to_string().map_err(|_| {
+            QueryError::ParseError(format!(
+                "channel name must be a symbol, found {:?}",
+                channel_data_type_name
+            ))
+        })
+    }
+
+    fn get_binary_exprs(unary_func_expr: &UnaryFuncExpr) -> Vec<&BinaryExpr> {
+        unary_func_expr.get_binary_exprs().iter().flatten().collect()
+    }
+
+    fn get_symbols(binary_expression: &BinaryExpr) -> Vec<&String> {
+        let (left, right, _) = binary_expression.get();
+        let mut symbols = Vec::new();
+        for data_type in &[left, right] {
+            if let DataType::Symbol(symbol) = data_type {
+                symbols.push(symbol);
+            }
+        }
+        symbols
+    }
+
+    // To combine binary expressions of parser, operations and conditions of condition
+    // clause as conjunction, condition is mapped to predicate: or - Or, not - Not
+    fn get_predicate(condition: ChannelCondition) -> Predicate {
+        let (binary_exprs, operations, conditions) = condition;
+        let mut predicates: Vec<Predicate> = binary_exprs.into_iter().map(Predicate::Expr).collect();
+        predicates.extend(operations.into_iter().map(Predicate::Operation));
+        predicates.extend(conditions.into_iter().map(Condition::to_predicate));
+        Predicate::And(predicates)
+    }
+
+    // To get name of variable of channel by name of query:
+    // qualified name (my_node.c) is variable of its channel only
+    fn get_own_name(channel_name: &str, name: &str) -> Option<String> {
+        match name.split_once('.') {
+            Some((own_channel_name, own_name)) if own_channel_name == channel_name => Some(own_name.to_string()),
+            Some(_) => None,
+            None => Some(name.to_string()),
+        }
+    }
+
+    // To get names of channels of function, every channel must exist
+    fn get_existing_channel_names<'a, C: ChannelAccess>(
+        channels: &C,
+        unary_func_expr: &'a UnaryFuncExpr,
+    ) -> Result<Vec<&'a String>, QueryError> {
+        let mut channel_names: Vec<&String> = Vec::new();
+        for channel_data_type_name in unary_func_expr.get_channel_names() {
+            let channel_name = Self::get_channel_name(channel_data_type_name)?;
+            if channels.get_table(channel_name).is_none() {
+                return Err(QueryError::UnknownChannel(channel_name.to_string()));
+            }
+            channel_names.push(channel_name);
+        }
+        Ok(channel_names)
+    }
+
+    // To find position of channel of symbol in channel names:
+    // channel of qualified symbol (my_node.c) or the only one which has variable
+    fn bind_symbol<C: ChannelAccess>(
+        channels: &C,
+        channel_names: &[&String],
+        symbol: &str,
+    ) -> Result<usize, QueryError> {
+        let is_var_exist = |channel_name: &String, name: &str| {
+            matches!(channels.get_table(channel_name), Some(mem_table) if mem_table.is_var_exist(&name.to_string()))
+        };
+        if let Some((channel_name, name)) = symbol.split_once('.') {
+            let position = channel_names
+                .iter()
+                .position(|listed_name| listed_name.as_str() == channel_name)
+                .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+            if !is_var_exist(channel_names[position], name) {
+                return Err(QueryError::UnknownVariable(symbol.to_string()));
+            }
+            return Ok(position);
+        }
+        let positions: Vec<usize> = channel_names
+            .iter()
+            .enumerate()
+            .filter(|(_, channel_name)| is_var_exist(channel_name, symbol))
+            .map(|(position, _)| position)
+            .collect();
+        match positions.as_slice() {
+            [position] => Ok(*position),
+            [] => Err(QueryError::UnknownVariable(symbol.to_string())),
+            _ => Err(QueryError::AmbiguousVariable(symbol.to_string())),
+        }
+    }
+
+    // To find position of the only channel of symbols, None if there are no symbols
+    fn bind_symbols<C: ChannelAccess>(
+        channels: &C,
+        channel_names: &[&String],
+        symbols: &[&str],
+    ) -> Result<Option<usize>, QueryError> {
+        let mut positions: Vec<usize> = Vec::new();
+        for symbol in symbols {
+            positions.push(Self::bind_symbol(channels, channel_names, symbol)?);
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        match positions.as_slice() {
+            [] => Ok(None),
+            [position] => Ok(Some(*position)),
+            _ => Err(QueryError::ParseError(
+                "expression compares variables of different channels, join them by onRead".to_string(),
+            )),
+        }
+    }
+
+    // To bind expressions, operations and conditions of condition to channels of their variables.
+    // Returns condition of every channel in order of names, None for channel
+    // without own expressions when condition isn't empty (it's skipped by query).
+    fn bind_condition<'a, C: ChannelAccess>(
+        channels: &C,
+        channel_names: &[&String],
+        binary_exprs: &[&'a BinaryExpr],
+        condition_clause: &'a ConditionClause,
+    ) -> Result<Vec<Option<ChannelCondition<'a>>>, QueryError> {
+        let mut conditions: Vec<ChannelCondition> = vec![(Vec::new(), Vec::new(), Vec::new()); channel_names.len()];
+        for binary_expr in binary_exprs {
+            let symbols: Vec<&str> = Self::get_symbols(binary_expr).into_iter().map(String::as_str).collect();
+            match Self::bind_symbols(channels, channel_names, &symbols)? {
+                // expression without variables is applied to every channel
+                None => conditions.iter_mut().for_each(|(exprs, _, _)| exprs.push(binary_expr)),
+                Some(position) => conditions[position].0.push(binary_expr),
+            }
+        }
+        for operation in condition_clause.get_operations() {
+            let position = Self::bind_symbol(channels, channel_names, operation.get_name())?;
+            conditions[position].1.push(operation);
+        }
+        // variables of disjunction or negation must be of one channel
+        for condition in condition_clause.get_conditions() {
+            match Self::bind_symbols(channels, channel_names, &condition.get_names())? {
+                None => conditions.iter_mut().for_each(|(_, _, own)| own.push(condition)),
+                Some(position) => conditions[position].2.push(condition),
+            }
+        }
+        let is_empty = binary_exprs.is_empty()
+            && condition_clause.get_operations().is_empty()
+            && condition_clause.get_conditions().is_empty();
+        Ok(conditions
+            .into_iter()
+            .map(|condition| {
+                let (exprs, operations, own) = &condition;
+                if is_empty || !exprs.is_empty() || !operations.is_empty() || !own.is_empty() {
+                    Some(condition)
+                } else {
+                    None
+                }
+            })
+            .collect())
+    }
+
+    // mutating query is logged with logic times of its channels before it's applied
+    fn write_log<C: ChannelAccess>(
+        channels: &C,
+        append_log: Option<&mut AppendLog>,
+        channel_names: &[&String],
+        line: &str,
+    ) -> Result<(), QueryError> {
+        let append_log = match append_log {
+            Some(append_log) => append_log,
+            None => return Ok(()),
+        };
+        let mut logic_times = Vec::new();
+        for channel_name in channel_names {
+            let logic_time = channels
+                .get_table(channel_name)
+                .map(|mem_table| mem_table.get_logic_time())
+                .unwrap_or(0);
+            logic_times.push((channel_name.to_string(), logic_time));
+        }
+        append_log(&WalRecord::new(logic_times, line.to_string()))
+    }
+
+    // To get types annotated by variables of onCreate (c:int = 1) from query text,
+    // query parser keeps only their values. Variable without annotation
+    // or with type unknown to schema gets type of its first value.
+    fn get_declared_types(query: &str) -> Vec<(String, ValueType)> {
+        let function_name = query.split('(').next().unwrap_or("").trim();
+        let (start, end) = match (function_name, find_groups(query).get(1)) {
+            ("onCreate", Some(group)) => *group,
+            _ => return Vec::new(),
+        };
+        split_top_level(&query[start..end], ',')
+            .into_iter()
+            .filter_map(|var| var.split('=').next()?.split_once(':'))
+            .filter_map(|(name, type_name)| Some((name.trim().to_string(), ValueType::from_name(type_name.trim())?)))
+            .collect()
+    }
+
+    // To merge variables into row, later value of variable replaces earlier one
+    fn merge_row(row: &mut Vec<(String, DataType)>, vars: &[DataVar]) {
+        for var in vars {
+            let (name, value) = var.get();
+            match row.iter_mut().find(|(row_name, _)| row_name == name) {
+                Some((_, row_value)) => *row_value = value.clone(),
+                None => row.push((name.to_string(), value.clone())),
+            }
+        }
+    }
+
+    // Statements are resolved one by one against staged channels - current rows of channels
+    // of transaction, so every statement sees writes of earlier ones. Writes of channel are
+    // merged into one row, deletes tombstone states from before transaction: onDelete of channel
+    // must precede its writes and channel is created once. Nothing is changed until all
+    // statements and rows are valid. Then transaction is logged and committed:
+    // states are tombstoned and row is inserted with one logic time.
+    fn resolve_transaction<C: ChannelAccess>(
+        channels: &mut C,
+        append_log: Option<&mut AppendLog>,
+        transaction: &Transaction,
+        line: &str,
+    ) -> Result<QueryResponse, QueryError> {
+        let mut staged = MemoryChannel::new();
+        let mut created: BTreeSet<String> = BTreeSet::new();
+        let mut changes: BTreeMap<String, (Vec<(String, DataType)>, IntervalSet)> = BTreeMap::new();
+        let mut declared_types: BTreeMap<String, Vec<(String, ValueType)>> = BTreeMap::new();
+        for statement in transaction.get_statements() {
+            let (query, condition_clause) = ConditionClause::split(statement)?;
+            let types = Self::get_declared_types(&query);
+            let ast = Self::parse(query)?;
+            if ast.len() != 1 {
+                return Err(QueryError::ParseError(format!(
+                    "one function per statement expected: {}",
+                    statement
+                )));
+            }
+            let unary_func_expr = &ast[0];
+            let func_type = unary_func_expr.get_func_type();
+            let channel_names = unary_func_expr
+                .get_channel_names()
+                .iter()
+                .map(Self::get_channel_name)
+                .collect::<Result<Vec<&String>, QueryError>>()?;
+            for channel_name in &channel_names {
+                if staged.contains_key(*channel_name) {
+                    continue;
+                }
+                let staged_table = match channels.get_table(channel_name) {
+                    Some(mem_table) => mem_table.get_current_row_table(&IntervalSet::new()),
+                    None if matches!(func_type, FuncType::OnCreate) => MemoryTable::init(),
+                    None => return Err(QueryError::UnknownChannel(channel_name.to_string())),
+                };
+                staged.insert(channel_name.to_string(), staged_table);
+            }
+            let conditions = match func_type {
+                FuncType::OnUpdate => {
+                    let binary_exprs = Self::get_binary_exprs(unary_func_expr);
+                    Self::bind_condition(&staged, &channel_names, &binary_exprs, &condition_clause)?
+                }
+                FuncType::OnDelete => {
+                    let channel_names = Self::get_existing_channel_names(channels, unary_func_expr)?;
+                    let binary_exprs = Self::get_binary_exprs(unary_func_expr);
+                    Self::bind_condition(channels, &channel_names, &binary_exprs, &condition_clause)?
+                }
+                _ => vec![None; channel_names.len()],
+            };
+            for (channel_name, condition) in channel_names.into_iter().zip(conditions) {
+                let (row, deleted) = changes.entry(channel_name.to_string()).or_default();
+                let predicate = condition.map(Self::get_predicate);
+                let staged_table = staged
+                    .get_mut(channel_name)
+                    .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+                match func_type {
+                    FuncType::OnCreate => {
+                        let vars = unary_func_expr.get_vars().as_ref().ok_or_else(|| {
+                            QueryError::ParseError("onCreate requires variables".to_string())
+                        })?;
+                        if !created.insert(channel_name.to_string()) {
+                            return Err(QueryError::ParseError(format!(
+                                "channel is created twice in transaction: {}",
+                                channel_name
+                            )));
+                        }
+                        let staged_row = vars
+                            .iter()
+                            .map(|var| {
+                                let (name, value) = var.get();
+                                (name.as_str(), value.clone())
+                            })
+                            .collect();
+                        staged_table.insert_declared_row(staged_row, &types)?;
+                        Self::merge_row(row, vars);
+                        declared_types.insert(channel_name.to_string(), types.clone());
+                    }
+                    FuncType::OnUpdate => {
+                        let vars = unary_func_expr.get_vars().as_ref().ok_or_else(|| {
+                            QueryError::ParseError("onUpdate requires variables".to_string())
+                        })?;
+                        let is_updated = match predicate {
+                            Some(predicate) => staged_table.update_by_predicate(&predicate, vars)?,
+                            None => false,
+                        };
+                        if is_updated {
+                            Self::merge_row(row, vars);
+                        }
+                    }
+                    FuncType::OnDelete => {
+                        if !row.is_empty() {
+                            return Err(QueryError::ParseError(format!(
+                                "onDelete must precede writes of channel in transaction: {}",
+                                channel_name
+                            )));
+                        }
+                        let mem_table = channels
+                            .get_table(channel_name)
+                            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+                        if let Some(predicate) = predicate {
+                            let indexes = mem_table.find_indexes_by_condition(&predicate)?;
+                            *deleted = deleted.union(&indexes);
+                            *staged_table = mem_table.get_current_row_table(deleted);
+                        }
+                    }
+                    _ => {
+                        return Err(QueryError::ParseError(
+                            "transaction supports only onCreate, onUpdate and onDelete".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let no_types = Vec::new();
+        for (channel_name, (row, _)) in changes.iter() {
+            if let Some(mem_table) = channels.get_table(channel_name) {
+                let types = declared_types.get(channel_name).unwrap_or(&no_types);
+                mem_table.check_row(row.iter().map(|(name, value)| (name.as_str(), value.clone())).collect(), types)?;
+            }
+        }
+
+        let channel_names: Vec<&String> = changes.keys().collect();
+        Self::write_log(channels, append_log, &channel_names, line)?;
+        for (channel_name, (row, deleted)) in changes.iter() {
+            let mem_table = channels.get_or_create_table(channel_name)?;
+            if !deleted.is_empty() {
+                mem_table.delete_indexes(deleted);
+            }
+            if !row.is_empty() {
+                let row = row.iter().map(|(name, value)| (name.as_str(), value.clone())).collect();
+                mem_table.insert_declared_row(row, declared_types.get(channel_name).unwrap_or(&no_types))?;
+            }
+        }
+        Ok(QueryResponse::None)
+    }
+
+    // Join of two channels of onRead: rows of each channel are found by expressions,
+    // operations and conditions bound to it, then matched by join expressions.
+    // Window is applied to joined rows, i.e. to logic times of left channel,
+    // projection is by qualified names of columns.
+    fn resolve_join<C: ChannelAccess>(
+        channels: &C,
+        channel_names: &[&String],
+        join_clause: &JoinClause,
+        binary_expressions: &[&BinaryExpr],
+        condition_clause: &ConditionClause,
+        read_clause: &ReadClause,
+    ) -> Result<ResultSet, QueryError> {
+        if read_clause.get_aggregates().is_some() {
+            return Err(QueryError::ParseError("aggregates are not supported by join".to_string()));
+        }
+        if channel_names.len() != 2 {
+            return Err(QueryError::ParseError("join requires two channels".to_string()));
+        }
+        let get_table = |channel_name: &str| {
+            channels
+                .get_table(&channel_name.to_string())
+                .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))
+        };
+        for join_expr in join_clause.get_join_exprs() {
+            for (channel_name, name) in [join_expr.get_left(), join_expr.get_right()] {
+                if !get_table(channel_name)?.is_var_exist(&name.to_string()) {
+                    return Err(QueryError::UnknownVariable(format!("{}.{}", channel_name, name)));
+                }
+            }
+        }
+
+        // channel without own expressions is joined by all its rows,
+        // left channel of join is listed first
+        let conditions = Self::bind_condition(channels, channel_names, binary_expressions, condition_clause)?;
+        let mut inputs: Vec<JoinInput> = Vec::new();
+        for (channel_name, condition) in channel_names.iter().zip(conditions) {
+            let mem_table = get_table(channel_name)?;
+            let predicate = Self::get_predicate(condition.unwrap_or_default());
+            inputs.push(JoinInput {
+                channel_name,
+                result_set: mem_table.find_rows_by_condition(&predicate, None)?,
+            });
+        }
+
+        let mut result = join_clause.join(&inputs[0], &inputs[1]);
+        if let Some(window) = read_clause.get_window() {
+            result = result.restrict(&window.to_interval_set());
+        }
+        if let Some(projection) = read_clause.get_projection() {
+            if let Some(name) = projection.iter().find(|name| result.get_column_index(name).is_none()) {
+                return Err(QueryError::UnknownVariable(name.to_string()));
+            }
+            result = result.project(projection).unwrap_or_default();
+        }
+        Ok(result)
+    }
+
+    // onChange(channel, ...)(condition) registers subscription on every channel,
+    // condition is resolved as of onRead: expressions are applied only to channels
+    // of their variables. Query isn't given to parser, its groups are parsed directly.
+    // Subscription isn't logged, it lives with subscriber.
+    fn resolve_subscription<C: ChannelAccess>(channels: &mut C, line: &str) -> Result<QueryResponse, QueryError> {
+        let line = line.trim();
+        let groups = find_groups(line);
+        let (channels_group, condition_group) = match groups.as_slice() {
+            [channels_group, condition_group]
+                if line[..channels_group.0 - 1].trim() == "onChange"
+                    && line[channels_group.1 + 1..condition_group.0 - 1].trim().is_empty()
+                    && condition_group.1 + 1 == line.len() =>
+            {
+                (&line[channels_group.0..channels_group.1], &line[condition_group.0..condition_group.1])
+            }
+            _ => {
+                let message = "onChange(<channel>, ...)(<condition>) expected".to_string();
+                return Err(QueryError::ParseError(message));
+            }
+        };
+        let mut own_channel_names: Vec<String> = Vec::new();
+        for channel_name in split_top_level(channels_group, ',') {
+            if !ConditionClause::is_name(channel_name) {
+                return Err(QueryError::ParseError(format!("channel name expected, found '{}'", channel_name)));
+            }
+            let channel_name = channel_name.to_string();
+            if channels.get_table(&channel_name).is_none() {
+                return Err(QueryError::UnknownChannel(channel_name));
+            }
+            own_channel_names.push(channel_name);
+        }
+        let channel_names: Vec<&String> = own_channel_names.iter().collect();
+        let condition_clause = ConditionClause::parse(condition_group)?;
+        let conditions = Self::bind_condition(channels, &channel_names, &[], &condition_clause)?;
+
+        let subscriber = Subscriber::new();
+        for (channel_name, condition) in channel_names.into_iter().zip(conditions) {
+            // subscription owns copy of condition of its channel
+            let (binary_exprs, operations, conditions) = match condition {
+                Some(condition) => condition,
+                None => continue,
+            };
+            let mut own_conditions: Vec<Condition> = binary_exprs.into_iter().map(Condition::from).collect();
+            own_conditions.extend(operations.into_iter().cloned().map(Condition::Operation));
+            own_conditions.extend(conditions.into_iter().cloned());
+            let mem_table = channels
+                .get_table_mut(channel_name)
+                .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+            mem_table.subscribe(subscriber.subscribe(channel_name, Condition::And(own_conditions)));
+        }
+        Ok(QueryResponse::Subscription(subscriber))
+    }
+
+    fn resolve_inner<C: ChannelAccess>(
+        channels: &mut C,
+        mut append_log: Option<&mut AppendLog>,
+        line: String,
+    ) -> Result<QueryResponse, QueryError> {
+        if let Some(result) = Introspection::resolve(channels, &line) {
+            return result.map(QueryResponse::ResultSet);
+        }
+        if let Some(alter_command) = AlterCommand::parse(&line) {
+            let alter_command = alter_command?;
+            let channel_names: Vec<&String> = alter_command.get_channel_names().iter().collect();
+            Self::write_log(channels, append_log, &channel_names, &line)?;
+            alter_command.apply(channels)?;
+            return Ok(QueryResponse::None);
+        }
+        if let Some(retention_command) = RetentionCommand::parse(&line) {
+            let retention_command = retention_command?;
+            let channel_names: Vec<&String> = retention_command.get_channel_names().iter().collect();
+            Self::write_log(channels, append_log, &channel_names, &line)?;
+            retention_command.apply(channels)?;
+            return Ok(QueryResponse::None);
+        }
+        if line.trim_start().starts_with("onChange") {
+            return Self::resolve_subscription(channels, &line);
+        }
+        if let Some(transaction) = Transaction::parse(&line) {
+            return Self::resolve_transaction(channels, append_log, &transaction?, &line);
+        }
+        let (line, read_clause) = ReadClause::split(&line)?;
+        let (query, join_clause) = JoinClause::split(&line)?;
+        let (query, condition_clause) = ConditionClause::split(&query)?;
+        let declared_types = Self::get_declared_types(&query);
+        let ast = Self::parse(query)?;
+        if ast.len() > 1 {
+            return Err(QueryError::ParseError(
+                "several functions must be in transaction(...)".to_string(),
+            ));
+        }
+        // query has at most one function here
+        if let Some(unary_func_expr) = ast.into_iter().next() {
             let func_type = unary_func_expr.get_func_type();
 
+            if !read_clause.is_empty() && !matches!(func_type, FuncType::OnRead) {
+                return Err(QueryError::ParseError(
+                    "read clause is supported only by onRead".to_string(),
+                ));
+            }
+
+            if matches!(func_type, FuncType::OnCreate | FuncType::OnUpdate | FuncType::OnDelete) {
+                let channel_names = unary_func_expr
+                    .get_channel_names()
+                    .iter()
+                    .map(Self::get_channel_name)
+                    .collect::<Result<Vec<&String>, QueryError>>()?;
+                Self::write_log(channels, append_log.take(), &channel_names, &line)?;
+            }
+
             match func_type {
                 FuncType::OnCreate => {
-                    let vars = unary_func_expr.get_vars().as_ref().unwrap();
+                    let vars = unary_func_expr.get_vars().as_ref().ok_or_else(|| {
+                        QueryError::ParseError("onCreate requires variables".to_string())
+                    })?;
                     for channel_data_type_name in unary_func_expr.get_channel_names() {
-                        let maybe_channel_name = channel_data_type_name.symbol_to_string();
-                        if maybe_channel_name.is_ok() {
-                            if let Ok(channel_name) = maybe_channel_name {
-                                mem_channel.insert(channel_name.to_string(), MemoryTable::init());
-                                let mem_table = mem_channel.get_mut(channel_name).unwrap();
-                                vars.iter().for_each(|var| {
-                                    let (name, value) = var.get();
-                                    mem_table.insert(name, value.clone());
-                                });
-                            }
-                        }
+                        let channel_name = Self::get_channel_name(channel_data_type_name)?;
+                        // onCreate of existing channel appends row to its table,
+                        // table isn't re-initialized and its history is kept
+                        let mem_table = channels.get_or_create_table(channel_name)?;
+                        // all variables of query are one row
+                        let row = vars
+                            .iter()
+                            .map(|var| {
+                                let (name, value) = var.get();
+                                (name.as_str(), value.clone())
+                            })
+                            .collect();
+                        mem_table.insert_declared_row(row, &declared_types)?;
                     }
-                    return QueryResponse::None;
+                    return Ok(QueryResponse::None);
                 },
 
                 FuncType::OnRead => {
-                    let mut result : Vec<PrintOfState> = Vec::new();
-                    for channel_data_type_name in unary_func_expr.get_channel_names() {
-                        let maybe_channel_name = channel_data_type_name.symbol_to_string();
-                        if maybe_channel_name.is_ok() {
-                            if let Ok(channel_name) = maybe_channel_name {
-                                let mem_table = mem_channel.get(&channel_name.to_string());
-                                if mem_table.is_some() {
-                                    for channel_binary_expressions in unary_func_expr.get_binary_exprs() {
-                                        for binary_expression in channel_binary_expressions {
-                                            let maybe_semi_result = mem_table.unwrap().find_by_predicate_intense(binary_expression);
-                                            if maybe_semi_result.is_some() {
-                                                result.append(maybe_semi_result.unwrap().borrow_mut());
-
-                                            }
-                                        }
-                                    }
+                    let mut result = ResultSet::default();
+                    let channel_names = Self::get_existing_channel_names(channels, &unary_func_expr)?;
+                    let binary_expressions = Self::get_binary_exprs(&unary_func_expr);
+                    if !join_clause.is_empty() {
+                        let result = Self::resolve_join(
+                            channels,
+                            &channel_names,
+                            &join_clause,
+                            &binary_expressions,
+                            &condition_clause,
+                            &read_clause,
+                        )?;
+                        return Ok(QueryResponse::ResultSet(result));
+                    }
+                    let conditions =
+                        Self::bind_condition(channels, &channel_names, &binary_expressions, &condition_clause)?;
+                    let mut mem_tables: Vec<&MemoryTable> = Vec::new();
+                    for channel_name in channel_names.iter() {
+                        let mem_table = channels
+                            .get_table(channel_name)
+                            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+                        mem_tables.push(mem_table);
+                    }
 
-                                }
+                    let projection = read_clause.get_projection();
+                    let aggregates = read_clause.get_aggregates();
+                    let projected_names: Vec<String> = match (projection, aggregates) {
+                        (Some(projection), _) => projection.to_vec(),
+                        (_, Some(aggregates)) => {
+                            aggregates.iter().map(|aggregate| aggregate.get_name().to_string()).collect()
+                        }
+                        _ => Vec::new(),
+                    };
+                    for name in projected_names.iter() {
+                        let is_var_exist = channel_names.iter().zip(mem_tables.iter()).any(|(channel_name, mem_table)| {
+                            let own_name = Self::get_own_name(channel_name, name);
+                            matches!(own_name, Some(own_name) if mem_table.is_var_exist(&own_name))
+                        });
+                        if !is_var_exist {
+                            return Err(QueryError::UnknownVariable(name.to_string()));
+                        }
+                    }
+                    // columns of several channels are qualified by channel: my_node.c
+                    let is_qualified = channel_names.len() > 1;
+                    if let (Some(projection), false) = (projection, is_qualified) {
+                        let names = projection.iter().filter_map(|name| Self::get_own_name(channel_names[0], name));
+                        result = ResultSet::new(names.collect());
+                    }
+                    if let (Some(aggregates), false) = (aggregates, is_qualified) {
+                        result = ResultSet::new(aggregates.iter().map(Aggregate::get_label).collect());
+                    }
 
+                    for ((channel_name, mem_table), condition) in channel_names.iter().zip(mem_tables).zip(conditions) {
+                        // expressions are applied only to channels of their variables
+                        let predicate = match condition {
+                            Some(condition) => Self::get_predicate(condition),
+                            None => continue,
+                        };
+                        let window = read_clause.get_window().map(|window| window.to_interval_set());
+                        let semi_result = match (projection, aggregates) {
+                            (_, Some(aggregates)) => {
+                                // table gives only aggregates of variables which it has
+                                let aggregates: Vec<Aggregate> = aggregates
+                                    .iter()
+                                    .filter(|aggregate| {
+                                        mem_table.is_var_exist(&aggregate.get_name().to_string())
+                                    })
+                                    .cloned()
+                                    .collect();
+                                if aggregates.is_empty() {
+                                    continue;
+                                }
+                                mem_table.aggregate_by_condition(&predicate, window.as_ref(), &aggregates)?
                             }
-
+                            (Some(projection), _) => {
+                                // table gives only projected variables which it has
+                                let names: Vec<String> = projection
+                                    .iter()
+                                    .filter_map(|name| Self::get_own_name(channel_name, name))
+                                    .filter(|name| mem_table.is_var_exist(name))
+                                    .collect();
+                                if names.is_empty() {
+                                    continue;
+                                }
+                                mem_table.find_projected_rows_by_condition(&predicate, window.as_ref(), &names)?
+                            }
+                            _ => mem_table.find_rows_by_condition(&predicate, window.as_ref())?,
+                        };
+                        if is_qualified {
+                            result.append(semi_result.qualify(channel_name));
+                        } else {
+                            result.append(semi_result);
                         }
+                    }
+                    return Ok(QueryResponse::ResultSet(result))
+                }
+
+                FuncType::OnUpdate => {
+                    let vars = unary_func_expr.get_vars().as_ref().ok_or_else(|| {
+                        QueryError::ParseError("onUpdate requires variables".to_string())
+                    })?;
+                    let channel_names = Self::get_existing_channel_names(channels, &unary_func_expr)?;
+                    let binary_exprs = Self::get_binary_exprs(&unary_func_expr);
+                    let conditions = Self::bind_condition(channels, &channel_names, &binary_exprs, &condition_clause)?;
+                    for (channel_name, condition) in channel_names.into_iter().zip(conditions) {
+                        let predicate = match condition {
+                            Some(condition) => Self::get_predicate(condition),
+                            None => continue,
+                        };
+                        let mem_table = channels
+                            .get_table_mut(channel_name)
+                            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+                        mem_table.update_by_predicate(&predicate, vars)?;
+                    }
+                    return Ok(QueryResponse::None);
+                }
 
+                FuncType::OnDelete => {
+                    let channel_names = Self::get_existing_channel_names(channels, &unary_func_expr)?;
+                    let binary_exprs = Self::get_binary_exprs(&unary_func_expr);
+                    let conditions = Self::bind_condition(channels, &channel_names, &binary_exprs, &condition_clause)?;
+                    for (channel_name, condition) in channel_names.into_iter().zip(conditions) {
+                        let predicate = match condition {
+                            Some(condition) => Self::get_predicate(condition),
+                            None => continue,
+                        };
+                        let mem_table = channels
+                            .get_table_mut(channel_name)
+                            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+                        mem_table.delete_by_predicate(&predicate)?;
                     }
-                    return QueryResponse::PrintOfStates(result)
+                    return Ok(QueryResponse::None);
+                }
+                _ => {
+                    return Err(QueryError::ParseError("unsupported function type".to_string()));
                 }
-                _ => {}
             }
         }
-       return  QueryResponse::None;
+        Ok(QueryResponse::None)
+    }
+
+    // public function
+    pub fn resolve(mem_channel: &mut MemoryChannel, line: String) -> Result<QueryResponse, QueryError> {
+        Self::resolve_inner(mem_channel, None, line)
+    }
+
+    // Same as resolve, but mutating query is written to log before it's applied
+    pub fn resolve_with_log(
+        mem_channel: &mut MemoryChannel,
+        wal: &mut WriteAheadLog,
+        line: String,
+    ) -> Result<QueryResponse, QueryError> {
+        Self::resolve_inner(mem_channel, Some(&mut |record: &WalRecord| wal.append(record)), line)
+    }
+
+    // Same as resolve, but over any access to channels,
+    // record of mutating query is passed to append_log before query is applied
+    pub fn resolve_channels<C: ChannelAccess>(
+        channels: &mut C,
+        append_log: Option<&mut AppendLog>,
+        line: String,
+    ) -> Result<QueryResponse, QueryError> {
+        Self::resolve_inner(channels, append_log, line)
     }
 }
 
+#[cfg(test)]
 mod test {
     use crate::memory::memory_channel::MemoryChannel;
+    use crate::memory::print_of_state::PrintOfState;
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::memory::retention_policy::RetentionPolicy;
+    use crate::memory::schema::ValueType;
+    use crate::error::query_error::QueryError;
     use crate::query::query_resolver::{QueryResolver,QueryResponse};
+    use qdb_ast::ast::types::DataType;
+    use std::ops::RangeInclusive;
+
+    fn read_rows(mem_channel: &mut MemoryChannel, line: &str) -> ResultSet {
+        match QueryResolver::resolve(mem_channel, line.to_string()) {
+            Ok(QueryResponse::ResultSet(result)) => result,
+            _ => panic!("onRead must return result set"),
+        }
+    }
+
+    fn read_logic_times(mem_channel: &mut MemoryChannel, line: &str) -> Vec<RangeInclusive<i64>> {
+        let result = read_rows(mem_channel, line);
+        result.get_rows().iter().map(|row| row.get_logic_times().clone()).collect()
+    }
 
     #[test]
     fn test_query_resolver_resolve() {
         let mut a = MemoryChannel::new();
-       QueryResolver::resolve(
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c > 0)");
+        assert_eq!(&["c".to_string()], result.get_columns());
+        assert_eq!(&[Row::new(0..=0, vec![DataType::Int(2)])], result.get_rows());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_update() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onUpdate(my_node)(c == 2)(c = 5)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c == 5)");
+        assert_eq!(&[Row::new(1..=1, vec![DataType::Int(5)])], result.get_rows());
+
+        // condition is matched with current row only, not with history
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onUpdate(my_node)(c == 2)(c = 5)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c == 5)");
+        assert_eq!(&[Row::new(1..=1, vec![DataType::Int(5)])], result.get_rows());
+        assert_eq!(4, a.get(&"my_node".to_string()).unwrap().get_logic_time());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_delete() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onDelete(my_node)(c == 2)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c == 2)");
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_errors() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+
+        let response = QueryResolver::resolve(&mut a, "onRead(other_node)(c > 0)".to_string());
+        assert_eq!(
+            Some(QueryError::UnknownChannel("other_node".to_string())),
+            response.err()
+        );
+
+        let response = QueryResolver::resolve(&mut a, "onRead(my_node)(d > 0)".to_string());
+        assert_eq!(
+            Some(QueryError::UnknownVariable("d".to_string())),
+            response.err()
+        );
+
+        let response = QueryResolver::resolve(&mut a, "onUpdate(my_node)(c > 0)(d = 1)".to_string());
+        assert_eq!(
+            Some(QueryError::UnknownVariable("d".to_string())),
+            response.err()
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_row_clock() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(a:int = 1, b:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(a:int = 3)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(a:int = 4, b:int = 5)".to_string()).unwrap();
+
+        let result = read_rows(&mut a, "onRead(my_node)(a == 3)");
+        assert_eq!(&["a".to_string(), "b".to_string()], result.get_columns());
+        assert_eq!(
+            &[Row::new(1..=1, vec![DataType::Int(3), DataType::Int(2)])],
+            result.get_rows()
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_create_existing() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(a:int = 1, b:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(a:int = 3, c:int = 4)".to_string()).unwrap();
+
+        let result = read_rows(&mut a, "onRead(my_node)()");
+        assert_eq!(&["a".to_string(), "b".to_string(), "c".to_string()], result.get_columns());
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(2), DataType::Null]),
+                Row::new(1..=1, vec![DataType::Int(3), DataType::Int(2), DataType::Int(4)]),
+            ],
+            result.get_rows()
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_conjunction() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2, d:int = 3)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = -1, d:int = 4)".to_string()).unwrap();
+
+        let result = read_rows(&mut a, "onRead(my_node)(c > 0, d < 5)");
+        assert_eq!(
+            &[Row::new(1..=1, vec![DataType::Int(2), DataType::Int(3)])],
+            result.get_rows()
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_or_not() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 4)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 7, d:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3, d:int = -1)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 9, d:int = -5)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(other_node)(e:int = 1)".to_string()).unwrap();
+
+        assert_eq!(vec![1..=1, 2..=2, 3..=3], read_logic_times(&mut a, "onRead(my_node)(c > 5 or d < 0)"));
+        assert_eq!(vec![0..=0, 2..=2], read_logic_times(&mut a, "onRead(my_node)(not(c > 5))"));
+        assert_eq!(vec![0..=0], read_logic_times(&mut a, "onRead(my_node)(not(c > 5 or d < 0))"));
+        assert_eq!(vec![1..=1, 3..=3], read_logic_times(&mut a, "onRead(my_node)(c > 2, not(c == 3))"));
+        assert_eq!(vec![0..=0, 3..=3], read_logic_times(&mut a, "onRead(my_node)(c in (1, 9) or (c > 5, d > 5))"));
+        let line = "onRead(my_node, other_node)(my_node.c == 7 or d == 4, c > 5)";
+        assert_eq!(vec![1..=1], read_logic_times(&mut a, line));
+        assert_eq!(
+            Err(QueryError::ParseError(
+                "expression compares variables of different channels, join them by onRead".to_string()
+            )),
+            QueryResolver::resolve(&mut a, "onRead(my_node, other_node)(c > 5 or e == 1)".to_string()).map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::UnknownVariable("f".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node)(c > 5 or f == 1)".to_string()).map(|_| ())
+        );
+
+        QueryResolver::resolve(&mut a, "onDelete(my_node)(c < 2 or d < -2)".to_string()).unwrap();
+        assert_eq!(vec![1..=1, 2..=2], read_logic_times(&mut a, "onRead(my_node)()"));
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_operations() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, e:text = \"apple\")".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2, e:text = \"banana\")".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3, e:text = \"apricot\")".to_string()).unwrap();
+
+        assert_eq!(vec![0..=0, 2..=2], read_logic_times(&mut a, "onRead(my_node)(c in (1, 3, 5))"));
+        assert_eq!(vec![1..=1], read_logic_times(&mut a, "onRead(my_node)(c > 1, c between 0 and 2)"));
+        assert_eq!(vec![0..=0, 2..=2], read_logic_times(&mut a, "onRead(my_node)(e like \"ap%\")"));
+        assert_eq!(vec![2..=2], read_logic_times(&mut a, "onRead(my_node)(e ~ \"c.t$\")"));
+        assert_eq!(
+            Err(QueryError::UnknownVariable("f".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node)(f in (1, 2))".to_string()).map(|_| ())
+        );
+
+        QueryResolver::resolve(&mut a, "onDelete(my_node)(c in (1, 2))".to_string()).unwrap();
+        assert_eq!(vec![2..=2], read_logic_times(&mut a, "onRead(my_node)()"));
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_schema() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        assert_eq!(
+            Err(QueryError::TypeMismatch("variable c is declared as int, found text".to_string())),
+            QueryResolver::resolve(&mut a, "onCreate(my_node)(c:text = \"a\")".to_string()).map(|_| ())
+        );
+        assert_eq!(1, a.get(&"my_node".to_string()).unwrap().get_logic_time());
+
+        QueryResolver::resolve(&mut a, "alter(my_node)(c:real, d:text)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2, d:text = \"a\")".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(d == \"a\")");
+        assert_eq!(
+            &[Row::new(1..=1, vec![DataType::Real(2.0), DataType::Text("a".to_string())])],
+            result.get_rows()
+        );
+
+        assert_eq!(
+            Err(QueryError::TypeMismatch("variable d of type text can't be changed to int".to_string())),
+            QueryResolver::resolve(&mut a, "alter(my_node)(d:int)".to_string()).map(|_| ())
+        );
+
+        // type is declared by annotation, not by first value
+        QueryResolver::resolve(&mut a, "onCreate(other_node)(e:real = 1)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "transaction(onCreate(new_node)(f:real = 2))".to_string()).unwrap();
+        for (channel_name, name) in &[("other_node", "e"), ("new_node", "f")] {
+            let schema = a.get(&channel_name.to_string()).unwrap().get_schema();
+            assert_eq!(Some(ValueType::Real), schema.get_type(name));
+        }
+        QueryResolver::resolve(&mut a, "onCreate(other_node)(e:real = 1.5)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(other_node)(e > 0)");
+        assert_eq!(
+            &[Row::new(0..=0, vec![DataType::Real(1.0)]), Row::new(1..=1, vec![DataType::Real(1.5)])],
+            result.get_rows()
+        );
+        assert_eq!(
+            Err(QueryError::TypeMismatch("variable e is declared as real, found text".to_string())),
+            QueryResolver::resolve(&mut a, "onCreate(other_node)(e:text = \"a\")".to_string()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_retention() {
+        let mut a = MemoryChannel::new();
+        for c in 1..=4 {
+            QueryResolver::resolve(&mut a, format!("onCreate(my_node)(c:int = {})", c)).unwrap();
+        }
+        QueryResolver::resolve(&mut a, "retain(my_node)(ticks:2)".to_string()).unwrap();
+        assert_eq!(
+            Some(RetentionPolicy::Ticks(2)),
+            a.get(&"my_node".to_string()).unwrap().get_retention()
+        );
+
+        let result = read_rows(&mut a, "onRead(my_node)(c > 0)");
+        assert_eq!(
+            &[
+                Row::new(2..=2, vec![DataType::Int(3)]),
+                Row::new(3..=3, vec![DataType::Int(4)])
+            ],
+            result.get_rows()
+        );
+
+        QueryResolver::resolve(&mut a, "retain(my_node)()".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "compact(my_node)".to_string()).unwrap();
+        assert_eq!(None, a.get(&"my_node".to_string()).unwrap().get_retention());
+        assert_eq!(
+            Err(QueryError::UnknownChannel("other_node".to_string())),
+            QueryResolver::resolve(&mut a, "compact(other_node)".to_string()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_variables() {
+        let mut a = MemoryChannel::new();
+        for (c, d) in &[(1, 2), (5, 3), (4, 4)] {
+            QueryResolver::resolve(&mut a, format!("onCreate(my_node)(c:int = {}, d:int = {})", c, d)).unwrap();
+        }
+
+        let result = read_rows(&mut a, "onRead(my_node)(c > d)");
+        assert_eq!(&[Row::new(1..=1, vec![DataType::Int(5), DataType::Int(3)])], result.get_rows());
+
+        QueryResolver::resolve(&mut a, "onDelete(my_node)(my_node.c == my_node.d)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c <= d)");
+        assert_eq!(&[Row::new(0..=0, vec![DataType::Int(1), DataType::Int(2)])], result.get_rows());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_qualified() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2, d:int = 20)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(other_node)(c:int = 2, e:int = 5)".to_string()).unwrap();
+
+        assert_eq!(
+            Err(QueryError::AmbiguousVariable("c".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node, other_node)(c > 1)".to_string()).map(|_| ())
+        );
+
+        let result = read_rows(&mut a, "onRead(my_node, other_node)(my_node.c > 1)");
+        assert_eq!(&[Row::new(1..=1, vec![DataType::Int(2), DataType::Int(20)])], result.get_rows());
+
+        // columns of several channels are qualified, so rows keep identity of channel,
+        // rows of channels are merged by logic time
+        let result = read_rows(&mut a, "onRead(my_node, other_node)(d > 10, e in (5))");
+        assert_eq!(
+            &[
+                "my_node.c".to_string(),
+                "my_node.d".to_string(),
+                "other_node.c".to_string(),
+                "other_node.e".to_string()
+            ],
+            result.get_columns()
+        );
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Null, DataType::Null, DataType::Int(2), DataType::Int(5)]),
+                Row::new(1..=1, vec![DataType::Int(2), DataType::Int(20), DataType::Null, DataType::Null])
+            ],
+            result.get_rows()
+        );
+
+        let result = read_rows(&mut a, "onRead(my_node, other_node)(d > 10, e in (5))[c]");
+        assert_eq!(&["my_node.c".to_string(), "other_node.c".to_string()], result.get_columns());
+
+        let line = "onRead(my_node, other_node)(d > 10, e in (5))[other_node.c, d]";
+        let result = read_rows(&mut a, line);
+        assert_eq!(&["my_node.d".to_string(), "other_node.c".to_string()], result.get_columns());
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Null, DataType::Int(2)]),
+                Row::new(1..=1, vec![DataType::Int(20), DataType::Null])
+            ],
+            result.get_rows()
+        );
+        assert_eq!(
+            Err(QueryError::UnknownVariable("other_node.d".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node, other_node)()[other_node.d]".to_string()).map(|_| ())
+        );
+
+        assert_eq!(
+            Err(QueryError::UnknownChannel("other_node".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node)(other_node.c > 0)".to_string()).map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::UnknownVariable("my_node.e".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(my_node)(my_node.e > 0)".to_string()).map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::ParseError(
+                "expression compares variables of different channels, join them by onRead".to_string()
+            )),
+            QueryResolver::resolve(
+                &mut a,
+                "onUpdate(my_node, other_node)(my_node.c == other_node.c)(d = 1)".to_string()
+            )
+            .map(|_| ())
+        );
+
+        // predicate of other_node doesn't delete states of my_node
+        QueryResolver::resolve(&mut a, "onDelete(my_node, other_node)(other_node.c == 2)".to_string()).unwrap();
+        let result = read_rows(&mut a, "onRead(my_node, other_node)()");
+        assert_eq!(2, result.get_rows().len());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_join() {
+        let mut a = MemoryChannel::new();
+        for (user_id, amount) in &[(1, 5), (2, 20), (1, 30)] {
+            let line = format!("onCreate(orders)(user_id:int = {}, amount:int = {})", user_id, amount);
+            QueryResolver::resolve(&mut a, line).unwrap();
+        }
+        for (id, name) in &[(1, "a"), (2, "b")] {
+            let line = format!("onCreate(users)(id:int = {}, name:text = \"{}\")", id, name);
+            QueryResolver::resolve(&mut a, line).unwrap();
+        }
+
+        let result = read_rows(&mut a, "onRead(orders, users)(orders.user_id == users.id)");
+        assert_eq!(
+            &[
+                "orders.amount".to_string(),
+                "orders.user_id".to_string(),
+                "users.id".to_string(),
+                "users.name".to_string()
+            ],
+            result.get_columns()
+        );
+        assert_eq!(
+            &[
+                Row::new(
+                    0..=0,
+                    vec![DataType::Int(5), DataType::Int(1), DataType::Int(1), DataType::Text("a".to_string())]
+                ),
+                Row::new(
+                    1..=1,
+                    vec![DataType::Int(20), DataType::Int(2), DataType::Int(2), DataType::Text("b".to_string())]
+                ),
+                Row::new(
+                    2..=2,
+                    vec![DataType::Int(30), DataType::Int(1), DataType::Int(1), DataType::Text("a".to_string())]
+                )
+            ],
+            result.get_rows()
+        );
+
+        let result = read_rows(
+            &mut a,
+            "onRead(orders, users)(users.id == orders.user_id, amount > 10)[orders.amount, users.name]",
+        );
+        assert_eq!(
+            &[
+                Row::new(1..=1, vec![DataType::Int(20), DataType::Text("b".to_string())]),
+                Row::new(2..=2, vec![DataType::Int(30), DataType::Text("a".to_string())])
+            ],
+            result.get_rows()
+        );
+
+        // window is applied to logic times of orders, users have own clock
+        let result = read_rows(
+            &mut a,
+            "onRead(orders, users)(orders.user_id != users.id)[orders.amount, users.name] at 2",
+        );
+        assert_eq!(
+            &[Row::new(2..=2, vec![DataType::Int(30), DataType::Text("b".to_string())])],
+            result.get_rows()
+        );
+
+        assert_eq!(
+            Err(QueryError::UnknownVariable("orders.id".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(orders, users)(orders.id == users.id)".to_string()).map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::UnknownVariable("orders.name".to_string())),
+            QueryResolver::resolve(&mut a, "onRead(orders, users)(orders.user_id == users.id)[orders.name]".to_string())
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::ParseError("aggregates are not supported by join".to_string())),
+            QueryResolver::resolve(
+                &mut a,
+                "onRead(orders, users)(orders.user_id == users.id)[max(amount)]".to_string()
+            )
+            .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_subscription() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 0)".to_string()).unwrap();
+        let subscriber = match QueryResolver::resolve(&mut a, "onChange(my_node)(c > 1)".to_string()) {
+            Ok(QueryResponse::Subscription(subscriber)) => subscriber,
+            _ => panic!("onChange must return subscriber"),
+        };
+        assert_eq!(1, a.get(&"my_node".to_string()).unwrap().get_subscriptions_count());
+
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        assert!(subscriber.get_receiver().try_recv().is_err());
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        let notification = subscriber.get_receiver().try_recv().unwrap();
+        assert_eq!(subscriber.get_id(), notification.get_subscription_id());
+        assert_eq!("my_node", notification.get_channel_name());
+        assert_eq!(2, notification.get_logic_time());
+        assert_eq!(
+            &[PrintOfState::new(&"c".to_string(), vec![DataType::Int(2)])][..],
+            notification.get_states()
+        );
+
+        assert_eq!(
+            Err(QueryError::UnknownChannel("other_node".to_string())),
+            QueryResolver::resolve(&mut a, "onChange(other_node)(c > 1)".to_string()).map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::UnknownVariable("d".to_string())),
+            QueryResolver::resolve(&mut a, "onChange(my_node)(d > 1)".to_string()).map(|_| ())
+        );
+
+        // subscription of dropped subscriber is removed on next write
+        drop(subscriber);
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3)".to_string()).unwrap();
+        assert_eq!(0, a.get(&"my_node".to_string()).unwrap().get_subscriptions_count());
+
+        // condition is evaluated at written row only
+        let query = "onChange(my_node)(not(c < 5) or c == 0)".to_string();
+        let subscriber = match QueryResolver::resolve(&mut a, query) {
+            Ok(QueryResponse::Subscription(subscriber)) => subscriber,
+            _ => panic!("onChange must return subscriber"),
+        };
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 4)".to_string()).unwrap();
+        assert!(subscriber.get_receiver().try_recv().is_err());
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 5)".to_string()).unwrap();
+        let notification = subscriber.get_receiver().try_recv().unwrap();
+        assert_eq!(5, notification.get_logic_time());
+        assert_eq!(
+            &[PrintOfState::new(&"c".to_string(), vec![DataType::Int(5)])][..],
+            notification.get_states()
+        );
+        assert!(subscriber.get_receiver().try_recv().is_err());
+
+        for line in &["onChange(my_node)", "onChange(my_node)(c > 1) (c > 2)", "onChange(my_node)(c > 1)[c]"] {
+            assert_eq!(
+                Err(QueryError::ParseError("onChange(<channel>, ...)(<condition>) expected".to_string())),
+                QueryResolver::resolve(&mut a, line.to_string()).map(|_| ())
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_transaction() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(other_node)(e:int = 1)".to_string()).unwrap();
+
+        QueryResolver::resolve(
+            &mut a,
+            "transaction(onCreate(my_node)(c:int = 2); onUpdate(my_node)(c == 2)(d = 20); \
+             onCreate(other_node)(e:int = 2))"
+                .to_string(),
+        )
+        .unwrap();
+        // statement sees writes of earlier ones, all writes of channel are one row
+        assert_eq!(2, a.get(&"my_node".to_string()).unwrap().get_logic_time());
+        assert_eq!(2, a.get(&"other_node".to_string()).unwrap().get_logic_time());
+        let result = read_rows(&mut a, "onRead(my_node)(c == 2)");
+        assert_eq!(&[Row::new(1..=1, vec![DataType::Int(2), DataType::Int(20)])], result.get_rows());
+
+        // rollback on error of any statement
+        assert_eq!(
+            Err(QueryError::TypeMismatch("variable e is declared as int, found text".to_string())),
+            QueryResolver::resolve(
+                &mut a,
+                "transaction(onCreate(my_node)(c:int = 3); onCreate(other_node)(e:text = \"x\"))".to_string()
+            )
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(QueryError::UnknownChannel("new_node".to_string())),
+            QueryResolver::resolve(
+                &mut a,
+                "transaction(onCreate(my_node)(c:int = 3); onDelete(new_node)(c == 1))".to_string()
+            )
+            .map(|_| ())
+        );
+        assert_eq!(2, a.get(&"my_node".to_string()).unwrap().get_logic_time());
+        assert_eq!(2, a.get(&"other_node".to_string()).unwrap().get_logic_time());
+
+        // deletes see states before transaction
+        QueryResolver::resolve(
             &mut a,
-            "onCreate(my_node)(c:int = 2)".to_string(),
+            "transaction(onDelete(my_node)(c == 2); onCreate(my_node)(c:int = 3, d:int = 30))".to_string(),
+        )
+        .unwrap();
+        let result = read_rows(&mut a, "onRead(my_node)(c > 0)");
+        assert_eq!(
+            &[
+                Row::new(0..=0, vec![DataType::Int(1), DataType::Int(10)]),
+                Row::new(2..=2, vec![DataType::Int(3), DataType::Int(30)])
+            ],
+            result.get_rows()
         );
-       let aa =  QueryResolver::resolve(
+
+        // update sees row created by transaction and current row after delete
+        QueryResolver::resolve(
             &mut a,
-            "onRead(my_node)(c > 0)".to_string(),
+            "transaction(onCreate(new_node)(f:int = 1); onUpdate(new_node)(f == 1)(f = 2); \
+             onDelete(my_node)(c == 3); onUpdate(my_node)(d == 10)(c = 4, d = 11))"
+                .to_string(),
+        )
+        .unwrap();
+        let result = read_rows(&mut a, "onRead(new_node)(f > 0)");
+        assert_eq!(&[Row::new(0..=0, vec![DataType::Int(2)])], result.get_rows());
+        let result = read_rows(&mut a, "onRead(my_node)(d == 11)");
+        assert_eq!(&[Row::new(3..=3, vec![DataType::Int(4), DataType::Int(11)])], result.get_rows());
+
+        for (line, message) in &[
+            (
+                "transaction(onRead(my_node)(c > 0))",
+                "transaction supports only onCreate, onUpdate and onDelete",
+            ),
+            (
+                "onCreate(my_node)(c:int = 4) onCreate(my_node)(c:int = 5)",
+                "unknown clause: onCreate(my_node)(c:int = 5)",
+            ),
+            (
+                "transaction(onCreate(my_node)(c:int = 4); onCreate(my_node)(c:int = 5))",
+                "channel is created twice in transaction: my_node",
+            ),
+            (
+                "transaction(onCreate(my_node)(c:int = 4); onDelete(my_node)(c == 4))",
+                "onDelete must precede writes of channel in transaction: my_node",
+            ),
+        ] {
+            assert_eq!(
+                Err(QueryError::ParseError(message.to_string())),
+                QueryResolver::resolve(&mut a, line.to_string()).map(|_| ())
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_time_window() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3, d:int = 30)".to_string()).unwrap();
+
+        let result = read_rows(&mut a, "onRead(my_node)(c > 0) at 1");
+        assert_eq!(
+            &[Row::new(1..=1, vec![DataType::Int(2), DataType::Int(10)])],
+            result.get_rows()
         );
 
-         if let QueryResponse::PrintOfStates(result) = aa {
-             println!("{:?}",result)
-         }
+        let result = read_rows(&mut a, "onRead(my_node)(c > 1) between 0 and 2");
+        assert_eq!(
+            &[
+                Row::new(1..=1, vec![DataType::Int(2), DataType::Int(10)]),
+                Row::new(2..=2, vec![DataType::Int(3), DataType::Int(30)]),
+            ],
+            result.get_rows()
+        );
+
+        assert_eq!(
+            Err(QueryError::ParseError("read clause is supported only by onRead".to_string())),
+            QueryResolver::resolve(&mut a, "onDelete(my_node)(c > 1) at 1".to_string()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_projection() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:int = 10, e:int = 0)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2, e:int = 1)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 3, d:int = 30)".to_string()).unwrap();
+
+        let result = read_rows(&mut a, "onRead(my_node)(c > 1)[e, d]");
+        assert_eq!(&["e".to_string(), "d".to_string()], result.get_columns());
+        assert_eq!(
+            &[
+                Row::new(1..=1, vec![DataType::Int(1), DataType::Int(10)]),
+                Row::new(2..=2, vec![DataType::Int(1), DataType::Int(30)]),
+            ],
+            result.get_rows()
+        );
+
+        let response = QueryResolver::resolve(&mut a, "onRead(my_node)(c > 1)[f]".to_string());
+        assert_eq!(Some(QueryError::UnknownVariable("f".to_string())), response.err());
+    }
+
+    #[test]
+    fn test_query_resolver_resolve_aggregates() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 1, d:real = 0.5)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 4)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 4, d:real = 2.5)".to_string()).unwrap();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 7)".to_string()).unwrap();
+
+        let result = read_rows(
+            &mut a,
+            "onRead(my_node)(c > 1)[count(c), count_distinct(c), sum(d), min(c), max(c), avg(d)]",
+        );
+        assert_eq!(
+            &[
+                "count(c)".to_string(),
+                "count_distinct(c)".to_string(),
+                "sum(d)".to_string(),
+                "min(c)".to_string(),
+                "max(c)".to_string(),
+                "avg(d)".to_string(),
+            ],
+            result.get_columns()
+        );
+        assert_eq!(
+            &[Row::new(
+                0..=3,
+                vec![
+                    DataType::Int(3),
+                    DataType::Int(2),
+                    DataType::Real(5.5),
+                    DataType::Int(4),
+                    DataType::Int(7),
+                    DataType::Real(5.5 / 3.0),
+                ]
+            )],
+            result.get_rows()
+        );
 
-        //println!("{:#?}", a);
+        let result = read_rows(&mut a, "onRead(my_node)()[max(c)] between 0 and 1");
+        assert_eq!(&[Row::new(0..=1, vec![DataType::Int(4)])], result.get_rows());
     }
 }
@@ -0,0 +1,153 @@
+use std::str::CharIndices;
+
+// round brackets group functions, conditions and lists of query
+pub const ROUND_BRACKETS: &[(char, char)] = &[('(', ')')];
+// square brackets also group projection of onRead
+pub const ALL_BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']')];
+
+// Scanner of symbols of query outside of text.
+// Text is in double quotes, '\' escapes next symbol inside text,
+// so quotes and brackets inside text are skipped.
+// Each symbol is given with its position and depth of brackets:
+// bracket is at depth of its group, content of group is one level deeper.
+// Example:
+// f(a, "b)") - f, ( and ) are at depth 0, a and , are at depth 1
+pub struct QueryScanner<'a> {
+    symbols: CharIndices<'a>,
+    brackets: &'static [(char, char)],
+    depth: i64,
+    is_text: bool,
+    is_escaped: bool,
+}
+
+impl<'a> QueryScanner<'a> {
+    // public function
+    pub fn new(text: &'a str, brackets: &'static [(char, char)]) -> Self {
+        QueryScanner {
+            symbols: text.char_indices(),
+            brackets,
+            depth: 0,
+            is_text: false,
+            is_escaped: false,
+        }
+    }
+
+    // depth after scanned symbols, it's negative if there are extra closing brackets
+    pub fn get_depth(&self) -> i64 {
+        self.depth
+    }
+
+    // To check that scanned symbols end inside text
+    pub fn is_text(&self) -> bool {
+        self.is_text
+    }
+}
+
+impl<'a> Iterator for QueryScanner<'a> {
+    type Item = (usize, char, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (position, symbol) in self.symbols.by_ref() {
+            if self.is_text {
+                match symbol {
+                    _ if self.is_escaped => self.is_escaped = false,
+                    '\\' => self.is_escaped = true,
+                    '"' => self.is_text = false,
+                    _ => {}
+                }
+                continue;
+            }
+            if symbol == '"' {
+                self.is_text = true;
+                continue;
+            }
+            if self.brackets.iter().any(|(open, _)| *open == symbol) {
+                self.depth += 1;
+                return Some((position, symbol, self.depth - 1));
+            }
+            if self.brackets.iter().any(|(_, close)| *close == symbol) {
+                self.depth -= 1;
+            }
+            return Some((position, symbol, self.depth));
+        }
+        None
+    }
+}
+
+// To get content ranges of top level bracket groups, brackets inside text are skipped
+pub fn find_groups(line: &str) -> Vec<(usize, usize)> {
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0;
+    for (position, symbol, depth) in QueryScanner::new(line, ROUND_BRACKETS) {
+        match symbol {
+            '(' if depth == 0 => start = position + 1,
+            ')' if depth == 0 => groups.push((start, position)),
+            _ => {}
+        }
+    }
+    groups
+}
+
+// To split text by separator outside of text and brackets.
+// Separator ' ' splits by any whitespace and keeps bracket group as own token.
+pub fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut items: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (position, symbol, depth) in QueryScanner::new(text, ROUND_BRACKETS) {
+        let is_separator = if separator == ' ' {
+            symbol.is_whitespace()
+        } else {
+            symbol == separator
+        };
+        match symbol {
+            _ if is_separator && depth == 0 => {
+                items.push(&text[start..position]);
+                start = position + symbol.len_utf8();
+            }
+            '(' if separator == ' ' && depth == 0 && start < position => {
+                items.push(&text[start..position]);
+                start = position;
+            }
+            _ => {}
+        }
+    }
+    items.push(&text[start..]);
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|item| !item.is_empty() || separator != ' ')
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::query_scanner::{find_groups, split_top_level, QueryScanner, ALL_BRACKETS, ROUND_BRACKETS};
+
+    #[test]
+    fn test_query_scanner() {
+        let symbols: Vec<(usize, char, i64)> = QueryScanner::new("f(a, \"b)\\\"\")", ROUND_BRACKETS).collect();
+        debug_assert_eq!(
+            vec![(0, 'f', 0), (1, '(', 0), (2, 'a', 1), (3, ',', 1), (11, ')', 0)],
+            symbols.into_iter().filter(|(_, symbol, _)| *symbol != ' ').collect::<Vec<_>>()
+        );
+
+        let mut scanner = QueryScanner::new("onRead(a)[max(b", ALL_BRACKETS);
+        scanner.by_ref().for_each(drop);
+        debug_assert_eq!(2, scanner.get_depth());
+        debug_assert!(!scanner.is_text());
+
+        let mut scanner = QueryScanner::new("onCreate(a)(b:text = \")", ALL_BRACKETS);
+        scanner.by_ref().for_each(drop);
+        debug_assert!(scanner.is_text());
+    }
+
+    #[test]
+    fn test_query_scanner_split() {
+        debug_assert_eq!(vec![(7, 14), (16, 24)], find_groups("onRead(my_node)(c == \"(\")"));
+        debug_assert_eq!(
+            vec!["c > 0", "d in (1, 2)", "e == \"a, b\""],
+            split_top_level("c > 0, d in (1, 2), e == \"a, b\"", ',')
+        );
+        debug_assert_eq!(vec!["not", "(c > 0)", "or", "d"], split_top_level("not(c > 0) or  d", ' '));
+    }
+}
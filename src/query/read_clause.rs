@@ -0,0 +1,226 @@
+use crate::memory::aggregate::{Aggregate, AggregateFunction};
+use crate::memory::memory_machine::IntervalSet;
+use crate::error::query_error::QueryError;
+use crate::query::query_scanner::{QueryScanner, ROUND_BRACKETS};
+
+// Window of logic time for onRead
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeWindow {
+    // state as of logic time
+    At(i64),
+    // history between logic times (inclusive)
+    Between(i64, i64),
+}
+
+impl TimeWindow {
+    pub fn to_interval_set(&self) -> IntervalSet {
+        match self {
+            TimeWindow::At(logic_time) => IntervalSet::from_range(*logic_time..=*logic_time),
+            TimeWindow::Between(from, to) => IntervalSet::from_range(*from..=*to),
+        }
+    }
+}
+
+// Clauses written after last bracket of onRead query:
+// projection of variables, then window.
+// Example:
+// onRead(my_node)(c > 0) at 5
+// onRead(my_node)(c > 0) between 2 and 7
+// onRead(my_node)(c > 0)[a, b] at 5
+// onRead(my_node)(c > 0)[count(a), max(b)] between 2 and 7
+#[derive(Debug, Default, PartialEq)]
+pub struct ReadClause {
+    projection: Option<Vec<String>>,
+    aggregates: Option<Vec<Aggregate>>,
+    window: Option<TimeWindow>,
+}
+
+impl ReadClause {
+    // hidden function
+    fn parse_logic_time(token: Option<&str>) -> Result<i64, QueryError> {
+        let token = token.ok_or_else(|| QueryError::ParseError("logic time expected".to_string()))?;
+        token
+            .parse::<i64>()
+            .map_err(|_| QueryError::ParseError(format!("logic time expected, found {}", token)))
+    }
+
+    fn parse_window(clause: &str) -> Result<TimeWindow, QueryError> {
+        let mut tokens = clause.split_whitespace();
+        let window = match tokens.next() {
+            Some("at") => TimeWindow::At(Self::parse_logic_time(tokens.next())?),
+            Some("between") => {
+                let from = Self::parse_logic_time(tokens.next())?;
+                if tokens.next() != Some("and") {
+                    return Err(QueryError::ParseError(
+                        "between requires 'and' keyword".to_string(),
+                    ));
+                }
+                let to = Self::parse_logic_time(tokens.next())?;
+                TimeWindow::Between(from, to)
+            }
+            _ => return Err(QueryError::ParseError(format!("unknown clause: {}", clause))),
+        };
+        if let Some(token) = tokens.next() {
+            return Err(QueryError::ParseError(format!("unexpected token: {}", token)));
+        }
+        Ok(window)
+    }
+
+    // query ends with bracket which is not followed by next bracket group,
+    // brackets inside text are skipped
+    fn find_query_end(line: &str) -> Option<usize> {
+        QueryScanner::new(line, ROUND_BRACKETS)
+            .find(|(position, symbol, depth)| {
+                *symbol == ')' && *depth == 0 && !line[position + 1..].trim_start().starts_with('(')
+            })
+            .map(|(position, _, _)| position + 1)
+    }
+
+    // variable of join is qualified by channel: users.name
+    fn parse_name(name: &str) -> Result<&str, QueryError> {
+        let is_name = !name.is_empty()
+            && name.chars().all(|symbol| symbol.is_alphanumeric() || symbol == '_' || symbol == '.');
+        if !is_name {
+            return Err(QueryError::ParseError(format!("variable name expected, found '{}'", name)));
+        }
+        Ok(name)
+    }
+
+    // item of projection is variable or aggregate of variable: a, max(a)
+    fn parse_projection(&mut self, clause: &str) -> Result<(), QueryError> {
+        let mut projection: Vec<String> = Vec::new();
+        let mut aggregates: Vec<Aggregate> = Vec::new();
+        let mut labels: Vec<String> = Vec::new();
+        for item in clause.split(',').map(str::trim) {
+            let label = match item.strip_suffix(')').and_then(|item| item.split_once('(')) {
+                Some((function_name, name)) => {
+                    let function = AggregateFunction::from_name(function_name.trim()).ok_or_else(|| {
+                        QueryError::ParseError(format!("unknown aggregate function: {}", function_name))
+                    })?;
+                    let aggregate = Aggregate::new(function, Self::parse_name(name.trim())?);
+                    let label = aggregate.get_label();
+                    aggregates.push(aggregate);
+                    label
+                }
+                None => {
+                    let name = Self::parse_name(item)?.to_string();
+                    projection.push(name.to_string());
+                    name
+                }
+            };
+            if labels.contains(&label) {
+                return Err(QueryError::ParseError(format!("duplicate item in projection: {}", label)));
+            }
+            labels.push(label);
+        }
+
+        if !projection.is_empty() && !aggregates.is_empty() {
+            return Err(QueryError::ParseError(
+                "variables and aggregates can't be mixed in projection".to_string(),
+            ));
+        }
+        if aggregates.is_empty() {
+            self.projection = Some(projection);
+        } else {
+            self.aggregates = Some(aggregates);
+        }
+        Ok(())
+    }
+
+    // public function
+    // To split query line to query for parser and clauses after it
+    pub fn split(line: &str) -> Result<(String, ReadClause), QueryError> {
+        let mut read_clause = ReadClause::default();
+        let query_end = match Self::find_query_end(line) {
+            Some(position) => position,
+            None => return Ok((line.to_string(), read_clause)),
+        };
+
+        let mut clause = line[query_end..].trim();
+        if let Some(rest) = clause.strip_prefix('[') {
+            let projection_end = rest
+                .find(']')
+                .ok_or_else(|| QueryError::ParseError("projection requires ']'".to_string()))?;
+            read_clause.parse_projection(&rest[..projection_end])?;
+            clause = rest[projection_end + 1..].trim();
+        }
+        if !clause.is_empty() {
+            read_clause.window = Some(Self::parse_window(clause)?);
+        }
+        Ok((line[..query_end].to_string(), read_clause))
+    }
+
+    pub fn get_window(&self) -> Option<&TimeWindow> {
+        self.window.as_ref()
+    }
+
+    // names of variables to read, all variables if None
+    pub fn get_projection(&self) -> Option<&[String]> {
+        self.projection.as_deref()
+    }
+
+    pub fn get_aggregates(&self) -> Option<&[Aggregate]> {
+        self.aggregates.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_none() && self.projection.is_none() && self.aggregates.is_none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::aggregate::{Aggregate, AggregateFunction};
+    use crate::error::query_error::QueryError;
+    use crate::query::read_clause::{ReadClause, TimeWindow};
+
+    #[test]
+    fn test_read_clause_split() {
+        let (line, read_clause) = ReadClause::split("onRead(my_node)(c > 0)").unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert!(read_clause.is_empty());
+
+        let (line, read_clause) = ReadClause::split("onRead(my_node)(c > 0) at 5").unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert_eq!(Some(&TimeWindow::At(5)), read_clause.get_window());
+
+        let (_, read_clause) =
+            ReadClause::split("onRead(my_node)(c > 0) between 2 and 7").unwrap();
+        debug_assert_eq!(Some(&TimeWindow::Between(2, 7)), read_clause.get_window());
+
+        debug_assert!(matches!(
+            ReadClause::split("onRead(my_node)(c > 0) between 2 or 7"),
+            Err(QueryError::ParseError(_))
+        ));
+
+        let (line, read_clause) = ReadClause::split("onRead(my_node)(c > 0)[a, b] at 5").unwrap();
+        debug_assert_eq!("onRead(my_node)(c > 0)", line);
+        debug_assert_eq!(
+            Some(&["a".to_string(), "b".to_string()][..]),
+            read_clause.get_projection()
+        );
+        debug_assert_eq!(Some(&TimeWindow::At(5)), read_clause.get_window());
+
+        let (_, read_clause) = ReadClause::split("onRead(my_node)(c > 0)[count(a), max( b )]").unwrap();
+        debug_assert_eq!(
+            Some(
+                &[
+                    Aggregate::new(AggregateFunction::Count, "a"),
+                    Aggregate::new(AggregateFunction::Max, "b")
+                ][..]
+            ),
+            read_clause.get_aggregates()
+        );
+        debug_assert_eq!(None, read_clause.get_projection());
+
+        for line in &[
+            "onRead(my_node)(c > 0)[]",
+            "onRead(my_node)(c > 0)[a, a]",
+            "onRead(my_node)(c > 0)[a",
+            "onRead(my_node)(c > 0)[a, max(b)]",
+            "onRead(my_node)(c > 0)[median(b)]",
+        ] {
+            debug_assert!(matches!(ReadClause::split(line), Err(QueryError::ParseError(_))));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::memory::memory_channel::ChannelAccess;
+use crate::memory::retention_policy::RetentionPolicy;
+use crate::error::query_error::QueryError;
+
+// Retention of history of channels, it's resolved without query parser.
+// Example:
+// retain(my_node)(ticks:1000)    - keep last 1000 logic ticks of channel
+// retain(my_node)(values:10)     - keep last 10 distinct values of each variable
+// retain(my_node)(watermark:500) - keep history from logic time 500
+// retain(my_node)()              - keep full history
+// compact(my_node)               - apply retention and tombstones now
+#[derive(Debug, PartialEq)]
+pub enum RetentionCommand {
+    Retain(Vec<String>, Option<RetentionPolicy>),
+    Compact(Vec<String>),
+}
+
+impl RetentionCommand {
+    // hidden function
+    fn split_group(text: &str) -> Result<(&str, &str), QueryError> {
+        let text = text
+            .trim_start()
+            .strip_prefix('(')
+            .ok_or_else(|| QueryError::ParseError("'(' expected".to_string()))?;
+        let end = text
+            .find(')')
+            .ok_or_else(|| QueryError::ParseError("')' expected".to_string()))?;
+        Ok((&text[..end], &text[end + 1..]))
+    }
+
+    fn parse_channel_names(text: &str) -> Result<Vec<String>, QueryError> {
+        text.split(',')
+            .map(str::trim)
+            .map(|name| {
+                let is_name = !name.is_empty()
+                    && name.chars().all(|symbol| symbol.is_alphanumeric() || symbol == '_');
+                if !is_name {
+                    return Err(QueryError::ParseError(format!("channel name expected, found '{}'", name)));
+                }
+                Ok(name.to_string())
+            })
+            .collect()
+    }
+
+    fn check_end(rest: &str) -> Result<(), QueryError> {
+        if !rest.trim().is_empty() {
+            return Err(QueryError::ParseError(format!("unexpected text: {}", rest.trim())));
+        }
+        Ok(())
+    }
+
+    // public function
+    // Returns None if line is not retention command
+    pub fn parse(line: &str) -> Option<Result<RetentionCommand, QueryError>> {
+        let line = line.trim();
+        let (name, rest) = line.split_at(line.find('(')?);
+        let parse_retain = || -> Result<RetentionCommand, QueryError> {
+            let (channel_names, rest) = Self::split_group(rest)?;
+            let (policy, rest) = Self::split_group(rest)?;
+            Self::check_end(rest)?;
+            let policy = match policy.trim() {
+                "" => None,
+                policy => Some(policy.parse().map_err(QueryError::ParseError)?),
+            };
+            Ok(RetentionCommand::Retain(Self::parse_channel_names(channel_names)?, policy))
+        };
+        let parse_compact = || -> Result<RetentionCommand, QueryError> {
+            let (channel_names, rest) = Self::split_group(rest)?;
+            Self::check_end(rest)?;
+            Ok(RetentionCommand::Compact(Self::parse_channel_names(channel_names)?))
+        };
+        match name.trim() {
+            "retain" => Some(parse_retain()),
+            "compact" => Some(parse_compact()),
+            _ => None,
+        }
+    }
+
+    pub fn get_channel_names(&self) -> &[String] {
+        match self {
+            RetentionCommand::Retain(channel_names, _) => channel_names,
+            RetentionCommand::Compact(channel_names) => channel_names,
+        }
+    }
+
+    // Channel is compacted when its retention is set
+    pub fn apply<C: ChannelAccess>(&self, channels: &mut C) -> Result<(), QueryError> {
+        for channel_name in self.get_channel_names() {
+            if channels.get_table(channel_name).is_none() {
+                return Err(QueryError::UnknownChannel(channel_name.to_string()));
+            }
+        }
+        for channel_name in self.get_channel_names() {
+            let mem_table = channels
+                .get_table_mut(channel_name)
+                .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))?;
+            if let RetentionCommand::Retain(_, policy) = self {
+                mem_table.set_retention(*policy);
+            }
+            mem_table.compact();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::retention_policy::RetentionPolicy;
+    use crate::error::query_error::QueryError;
+    use crate::query::retention_command::RetentionCommand;
+
+    #[test]
+    fn test_retention_command_parse() {
+        debug_assert_eq!(
+            Some(Ok(RetentionCommand::Retain(
+                vec!["my_node".to_string(), "other_node".to_string()],
+                Some(RetentionPolicy::Ticks(1000))
+            ))),
+            RetentionCommand::parse("retain(my_node, other_node)(ticks:1000)")
+        );
+        debug_assert_eq!(
+            Some(Ok(RetentionCommand::Retain(vec!["my_node".to_string()], None))),
+            RetentionCommand::parse("retain(my_node)()")
+        );
+        debug_assert_eq!(
+            Some(Ok(RetentionCommand::Compact(vec!["my_node".to_string()]))),
+            RetentionCommand::parse(" compact(my_node) ")
+        );
+        debug_assert_eq!(None, RetentionCommand::parse("onRead(my_node)()"));
+        for line in &[
+            "retain(my_node)",
+            "retain(my_node)(ticks:0)",
+            "retain(my_node)(watermark:-1)",
+            "retain(my_node)(days:1)",
+            "compact(my_node)(ticks:1)",
+            "compact()",
+        ] {
+            debug_assert!(matches!(RetentionCommand::parse(line), Some(Err(QueryError::ParseError(_)))));
+        }
+    }
+}
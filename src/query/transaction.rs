@@ -0,0 +1,80 @@
+use crate::error::query_error::QueryError;
+use crate::query::query_scanner::{find_groups, split_top_level};
+
+// Batch of mutating statements separated by ';', it's applied atomically by QueryResolver.
+// Every statement sees writes of earlier ones, writes of each channel are
+// merged into one row with one logic time (later value of variable wins),
+// deletes tombstone states from before transaction, so onDelete of channel
+// must precede its writes, and channel is created once per transaction.
+// Nothing is applied if any statement fails.
+// Example:
+// transaction(onCreate(my_node)(c:int = 1); onUpdate(other_node)(d == 1)(d = 2))
+#[derive(Debug, PartialEq)]
+pub struct Transaction {
+    statements: Vec<String>,
+}
+
+impl Transaction {
+    // public function
+    // Returns None if line is not transaction
+    pub fn parse(line: &str) -> Option<Result<Transaction, QueryError>> {
+        let rest = line.trim().strip_prefix("transaction")?.trim_start();
+        if !rest.starts_with('(') {
+            return None;
+        }
+
+        let parse = || -> Result<Transaction, QueryError> {
+            let groups = find_groups(rest);
+            let body = match groups.as_slice() {
+                [(start, end)] if *end == rest.len() - 1 => &rest[*start..*end],
+                _ => {
+                    return Err(QueryError::ParseError(
+                        "transaction requires one group of statements".to_string(),
+                    ))
+                }
+            };
+            let statements: Vec<String> = split_top_level(body, ';')
+                .into_iter()
+                .map(str::trim)
+                .filter(|statement| !statement.is_empty())
+                .map(str::to_string)
+                .collect();
+            if statements.is_empty() {
+                return Err(QueryError::ParseError("transaction requires statements".to_string()));
+            }
+            Ok(Transaction { statements })
+        };
+        Some(parse())
+    }
+
+    pub fn get_statements(&self) -> &[String] {
+        &self.statements
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::query_error::QueryError;
+    use crate::query::transaction::Transaction;
+
+    #[test]
+    fn test_transaction_parse() {
+        let transaction = Transaction::parse(
+            "transaction(onCreate(my_node)(e:text = \"a;b\"); onDelete(my_node)(c in (1, 2));)",
+        )
+        .unwrap()
+        .unwrap();
+        debug_assert_eq!(
+            &[
+                "onCreate(my_node)(e:text = \"a;b\")".to_string(),
+                "onDelete(my_node)(c in (1, 2))".to_string()
+            ][..],
+            transaction.get_statements()
+        );
+
+        debug_assert_eq!(None, Transaction::parse("onRead(my_node)()"));
+        for line in &["transaction()", "transaction(onRead(my_node)()) at 5", "transaction(onRead(my_node)()"] {
+            debug_assert!(matches!(Transaction::parse(line), Some(Err(QueryError::ParseError(_)))));
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod repl_session;
+pub mod table_view;
@@ -0,0 +1,277 @@
+use crate::memory::memory_channel::MemoryChannel;
+use crate::memory::predicate::Predicate;
+use crate::memory::subscription::Subscriber;
+use crate::query::query_resolver::{QueryResolver, QueryResponse};
+use crate::query::query_scanner::{QueryScanner, ALL_BRACKETS};
+use crate::repl::table_view::format_table;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::fs;
+
+const HELP: &str = "\
+.help               show this help
+.channels           list channels
+.vars <channel>     list variables of channel
+.dump <channel>     print full history of channel
+.load <file>        execute queries from file
+.exit               leave repl
+channels()          list channels with logic time and memory usage
+describe(<channel>) list variables of channel with their types and statistics
+alter(<channel>)(<name>:<type>, ...)
+                    add variables or widen their types (int -> real)
+retain(<channel>)(<ticks|values|watermark>:<number>)
+                    keep only part of history, retain(<channel>)() keeps all
+compact(<channel>)  apply retention and drop deleted history now
+onRead(<channel>)(<name> > 0 or <name> < 0, not(<name> == 1, ...))
+                    disjunction and negation, brackets group conjunction
+onRead(<channel>, <channel>)(<channel>.<name> > 0)
+                    condition of variable of one channel, variable which is
+                    in several channels of query must be qualified,
+                    columns of several channels are qualified by channel
+onRead(<channel>, <channel>)(<channel>.<name> == <channel>.<name>, ...)
+                    join rows of two channels, latest state of channel is used
+                    after its logic time
+onChange(<channel>)(<condition>)
+                    print rows written later where condition is true
+transaction(<statement>; ...)
+                    apply onCreate, onUpdate and onDelete at once or not at all
+Query can take several lines, it ends when all brackets are closed;
+line ending with \\ is always continued.
+";
+
+const HISTORY_FILE: &str = ".qdb_history";
+
+pub enum ReplOutput {
+    Text(String),
+    Exit,
+}
+
+// Interactive shell over in-process MemoryChannel.
+// Rows of onChange subscriptions are printed after query which wrote them.
+pub struct ReplSession {
+    mem_channel: MemoryChannel,
+    subscribers: Vec<Subscriber>,
+}
+
+impl ReplSession {
+    // hidden function
+    fn execute_query(&mut self, statement: &str) -> String {
+        let mut text = match QueryResolver::resolve(&mut self.mem_channel, statement.to_string()) {
+            Ok(QueryResponse::None) => "ok\n".to_string(),
+            Ok(QueryResponse::ResultSet(result_set)) => format_table(&result_set),
+            Ok(QueryResponse::Subscription(subscriber)) => {
+                let text = format!("subscribed {}\n", subscriber.get_id());
+                self.subscribers.push(subscriber);
+                text
+            }
+            Err(query_error) => format!("error: {}\n", query_error),
+        };
+        for subscriber in self.subscribers.iter() {
+            while let Ok(notification) = subscriber.get_receiver().try_recv() {
+                text.push_str(&format!(
+                    "change {} {}\n",
+                    notification.get_subscription_id(),
+                    notification.get_channel_name()
+                ));
+                text.push_str(&format_table(&notification.to_result_set()));
+            }
+        }
+        text
+    }
+
+    fn execute_meta(&mut self, command: &str, argument: Option<&str>) -> ReplOutput {
+        let text = match (command, argument) {
+            (".help", _) => HELP.to_string(),
+            (".exit", _) | (".quit", _) => return ReplOutput::Exit,
+            (".channels", _) => {
+                let mut text = String::new();
+                for (channel_name, _) in self.mem_channel.iter() {
+                    text.push_str(channel_name);
+                    text.push('\n');
+                }
+                if text.is_empty() {
+                    text.push_str("(no channels)\n");
+                }
+                text
+            }
+            (".vars", Some(channel_name)) => match self.mem_channel.get(&channel_name.to_string()) {
+                Some(mem_table) => mem_table
+                    .get_var_names()
+                    .iter()
+                    .map(|name| format!("{}\n", name))
+                    .collect(),
+                None => format!("error: unknown channel: {}\n", channel_name),
+            },
+            (".dump", Some(channel_name)) => match self.mem_channel.get(&channel_name.to_string()) {
+                Some(mem_table) => match mem_table.find_rows_by_condition(&Predicate::all(Vec::new()), None) {
+                    Ok(result_set) => format_table(&result_set),
+                    Err(query_error) => format!("error: {}\n", query_error),
+                },
+                None => format!("error: unknown channel: {}\n", channel_name),
+            },
+            (".load", Some(path)) => self.load(path),
+            (".vars", None) | (".dump", None) => format!("error: {} requires channel\n", command),
+            (".load", None) => "error: .load requires file\n".to_string(),
+            _ => format!("error: unknown command: {}, see .help\n", command),
+        };
+        ReplOutput::Text(text)
+    }
+
+    fn load(&mut self, path: &str) -> String {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) => return format!("error: can't read {}: {}\n", path, error),
+        };
+
+        let mut text = String::new();
+        let mut statement = String::new();
+        for line in content.lines() {
+            if statement.is_empty() && (line.trim().is_empty() || line.trim_start().starts_with('#')) {
+                continue;
+            }
+            statement.push_str(line.trim_end_matches('\\'));
+            statement.push(' ');
+            if line.ends_with('\\') || !Self::is_complete(&statement) {
+                continue;
+            }
+            text.push_str(&self.execute_query(statement.trim()));
+            statement.clear();
+        }
+        if !statement.trim().is_empty() {
+            text.push_str("error: unexpected end of file\n");
+        }
+        text
+    }
+
+    fn get_history_path() -> Option<String> {
+        std::env::var("HOME")
+            .ok()
+            .map(|home| format!("{}/{}", home, HISTORY_FILE))
+    }
+
+    // public function
+    pub fn new() -> Self {
+        ReplSession {
+            mem_channel: MemoryChannel::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    // Statement is complete when all brackets out of text are closed
+    pub fn is_complete(statement: &str) -> bool {
+        let mut scanner = QueryScanner::new(statement, ALL_BRACKETS);
+        scanner.by_ref().for_each(drop);
+        scanner.get_depth() <= 0 && !scanner.is_text()
+    }
+
+    // To execute meta command (starts with '.') or query
+    pub fn execute(&mut self, statement: &str) -> ReplOutput {
+        let statement = statement.trim();
+        if statement.is_empty() {
+            return ReplOutput::Text(String::new());
+        }
+        if statement.starts_with('.') {
+            let mut parts = statement.splitn(2, char::is_whitespace);
+            let command = parts.next().unwrap_or_default();
+            let argument = parts.next().map(str::trim).filter(|argument| !argument.is_empty());
+            return self.execute_meta(command, argument);
+        }
+        ReplOutput::Text(self.execute_query(statement))
+    }
+
+    // To read statements from terminal until .exit or end of input
+    pub fn run(&mut self) -> Result<(), String> {
+        let mut editor = DefaultEditor::new().map_err(|e| e.to_string())?;
+        let history_path = Self::get_history_path();
+        if let Some(history_path) = history_path.as_ref() {
+            let _ = editor.load_history(history_path);
+        }
+
+        let mut statement = String::new();
+        loop {
+            let prompt = if statement.is_empty() { "qdb> " } else { "...> " };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    statement.push_str(line.trim_end_matches('\\'));
+                    if line.ends_with('\\') || !Self::is_complete(&statement) {
+                        statement.push('\n');
+                        continue;
+                    }
+
+                    let _ = editor.add_history_entry(statement.as_str());
+                    let output = self.execute(&statement.replace('\n', " "));
+                    statement.clear();
+                    match output {
+                        ReplOutput::Text(text) => print!("{}", text),
+                        ReplOutput::Exit => break,
+                    }
+                }
+                // ctrl-c drops unfinished statement
+                Err(ReadlineError::Interrupted) => statement.clear(),
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+
+        if let Some(history_path) = history_path.as_ref() {
+            let _ = editor.save_history(history_path);
+        }
+        Ok(())
+    }
+}
+
+impl Default for ReplSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::repl::repl_session::{ReplOutput, ReplSession};
+    use std::fs;
+
+    fn get_text(output: ReplOutput) -> String {
+        match output {
+            ReplOutput::Text(text) => text,
+            ReplOutput::Exit => panic!("text output expected"),
+        }
+    }
+
+    #[test]
+    fn test_repl_session_is_complete() {
+        debug_assert!(ReplSession::is_complete("onRead(my_node)(c > 0)"));
+        debug_assert!(!ReplSession::is_complete("onRead(my_node)(c > 0,"));
+        debug_assert!(!ReplSession::is_complete("onCreate(my_node)(c:text = \")"));
+        debug_assert!(ReplSession::is_complete("onCreate(my_node)(c:text = \"(\")"));
+    }
+
+    #[test]
+    fn test_repl_session_execute() {
+        let mut session = ReplSession::new();
+        debug_assert_eq!("ok\n", get_text(session.execute("onCreate(my_node)(c:int = 2, d:int = 3)")));
+        debug_assert_eq!("my_node\n", get_text(session.execute(".channels")));
+        debug_assert_eq!("c\nd\n", get_text(session.execute(".vars my_node")));
+        debug_assert_eq!(
+            "+---+---+---+\n| t | c | d |\n+---+---+---+\n| 0 | 2 | 3 |\n+---+---+---+\n",
+            get_text(session.execute(".dump my_node"))
+        );
+        debug_assert_eq!(
+            "error: unknown channel: other_node\n",
+            get_text(session.execute(".vars other_node"))
+        );
+        debug_assert!(matches!(session.execute(".exit"), ReplOutput::Exit));
+    }
+
+    #[test]
+    fn test_repl_session_load() {
+        let path = std::env::temp_dir().join("qdb_repl_session_load.qdb");
+        fs::write(&path, "# my node\nonCreate(my_node)(\n  c:int = 2)\n\nonRead(my_node)(c > 0)\n").unwrap();
+
+        let mut session = ReplSession::new();
+        let text = get_text(session.execute(&format!(".load {}", path.display())));
+        fs::remove_file(&path).unwrap();
+
+        debug_assert_eq!("ok\n+---+---+\n| t | c |\n+---+---+\n| 0 | 2 |\n+---+---+\n", text);
+    }
+}
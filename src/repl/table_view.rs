@@ -0,0 +1,106 @@
+use crate::memory::result_set::ResultSet;
+use qdb_ast::ast::types::DataType;
+use std::ops::RangeInclusive;
+
+// To format value for humans, unlike wire format without type prefix
+pub fn format_value(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "null".to_string(),
+        DataType::Int(value) => value.to_string(),
+        DataType::Real(value) => value.to_string(),
+        DataType::Text(value) => format!("\"{}\"", value),
+        DataType::Symbol(value) => value.to_string(),
+    }
+}
+
+fn format_separator(widths: &[usize]) -> String {
+    let mut line = String::from("+");
+    for width in widths {
+        line.push_str(&"-".repeat(width + 2));
+        line.push('+');
+    }
+    line.push('\n');
+    line
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let mut line = String::from("|");
+    for (cell, width) in cells.iter().zip(widths) {
+        line.push_str(&format!(" {:<width$} |", cell, width = width));
+    }
+    line.push('\n');
+    line
+}
+
+// To format range of logic time of row: "3" or "3..5"
+pub fn format_logic_times(logic_times: &RangeInclusive<i64>) -> String {
+    if logic_times.start() == logic_times.end() {
+        logic_times.start().to_string()
+    } else {
+        format!("{}..{}", logic_times.start(), logic_times.end())
+    }
+}
+
+// To render result set as table, first column is logic time of row.
+// Example:
+// +------+---+----+
+// | t    | c | d  |
+// +------+---+----+
+// | 0    | 2 | 10 |
+// | 1..2 | 3 | 30 |
+// +------+---+----+
+pub fn format_table(result_set: &ResultSet) -> String {
+    if result_set.is_empty() {
+        return "(empty)\n".to_string();
+    }
+
+    let mut headers = vec!["t".to_string()];
+    headers.extend(result_set.get_columns().iter().cloned());
+    let rows: Vec<Vec<String>> = result_set
+        .get_rows()
+        .iter()
+        .map(|row| {
+            std::iter::once(format_logic_times(row.get_logic_times()))
+                .chain(row.get_values().iter().map(format_value))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|index| {
+            rows.iter()
+                .map(|cells| cells[index].chars().count())
+                .chain(std::iter::once(headers[index].chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = format_separator(&widths);
+    table.push_str(&format_row(&headers, &widths));
+    table.push_str(&format_separator(&widths));
+    for cells in rows.iter() {
+        table.push_str(&format_row(cells, &widths));
+    }
+    table.push_str(&format_separator(&widths));
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::repl::table_view::format_table;
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_format_table() {
+        let mut result_set = ResultSet::new(vec!["c".to_string(), "d".to_string()]);
+        result_set.push_row(Row::new(0..=0, vec![DataType::Int(2), DataType::Int(10)]));
+        result_set.push_row(Row::new(1..=2, vec![DataType::Int(3), DataType::Int(30)]));
+        debug_assert_eq!(
+            "+------+---+----+\n| t    | c | d  |\n+------+---+----+\n\
+             | 0    | 2 | 10 |\n| 1..2 | 3 | 30 |\n+------+---+----+\n",
+            format_table(&result_set)
+        );
+        debug_assert_eq!("(empty)\n", format_table(&ResultSet::default()));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod tcp_server;
+pub mod wire_format;
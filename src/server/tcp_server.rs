@@ -0,0 +1,304 @@
+use crate::memory::subscription::Subscriber;
+use crate::query::query_resolver::QueryResponse;
+use crate::server::wire_format;
+use crate::storage::concurrent_channel::ConcurrentChannel;
+use crate::storage::durable_channel::StorageConfig;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// how often blocked accept and read check shutdown flag
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub max_connections: usize,
+    // maximal length of query line in bytes with its line break
+    pub max_line_length: usize,
+    pub storage: StorageConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: "127.0.0.1:7070".to_string(),
+            max_connections: 64,
+            max_line_length: 1024 * 1024,
+            storage: StorageConfig::default(),
+        }
+    }
+}
+
+// Slot of open connection in count of active connections.
+// It's released on drop, so connection thread which panics frees its slot too.
+struct ConnectionSlot {
+    active_connections: Arc<AtomicUsize>,
+}
+
+impl ConnectionSlot {
+    fn take(active_connections: &Arc<AtomicUsize>) -> Self {
+        active_connections.fetch_add(1, Ordering::SeqCst);
+        ConnectionSlot {
+            active_connections: active_connections.clone(),
+        }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.active_connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// TCP server of newline-delimited queries.
+// Every connection resolves its queries on shared ConcurrentChannel,
+// so queries of different connections run in parallel unless they write same channels.
+pub struct TcpServer {
+    listener: TcpListener,
+    config: ServerConfig,
+}
+
+impl TcpServer {
+    // hidden function
+    // Channel is saved to snapshot when log is big enough and on stop.
+    // Subscriber of onChange is kept by connection.
+    fn execute(concurrent_channel: &ConcurrentChannel, subscribers: &mut Vec<Subscriber>, line: String) -> String {
+        let result = concurrent_channel.resolve(line);
+        let response = wire_format::encode_response(&result);
+        if let Ok(QueryResponse::Subscription(subscriber)) = result {
+            subscribers.push(subscriber);
+        }
+        if concurrent_channel.is_checkpoint_needed() {
+            if let Err(error) = concurrent_channel.checkpoint() {
+                eprintln!("qdb: checkpoint error: {}", error);
+            }
+        }
+        response
+    }
+
+    fn write_notifications(writer: &mut TcpStream, subscribers: &[Subscriber]) -> io::Result<()> {
+        for subscriber in subscribers {
+            while let Ok(notification) = subscriber.get_receiver().try_recv() {
+                writer.write_all(wire_format::encode_notification(&notification).as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(
+        stream: TcpStream,
+        concurrent_channel: Arc<ConcurrentChannel>,
+        shutdown: Arc<AtomicBool>,
+        max_line_length: usize,
+    ) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut subscribers: Vec<Subscriber> = Vec::new();
+
+        while !shutdown.load(Ordering::SeqCst) {
+            Self::write_notifications(&mut writer, &subscribers)?;
+            // buffer is shorter than limit here, so at least one byte is read
+            let limit = max_line_length.saturating_sub(buffer.len()) as u64;
+            match reader.by_ref().take(limit).read_until(b'\n', &mut buffer) {
+                // connection is closed by client
+                Ok(0) => break,
+                // line is too long, so its rest is not read and connection is closed
+                Ok(_) if !buffer.ends_with(b"\n") && buffer.len() >= max_line_length => {
+                    let message = format!("query line is longer than {} bytes", max_line_length);
+                    writer.write_all(wire_format::encode_error("line_too_long", &message).as_bytes())?;
+                    return Err(io::Error::new(ErrorKind::InvalidData, message));
+                }
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer)
+                        .trim_end_matches(['\n', '\r'])
+                        .to_string();
+                    buffer.clear();
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let response = Self::execute(&concurrent_channel, &mut subscribers, line);
+                    writer.write_all(response.as_bytes())?;
+                }
+                // read bytes stay in buffer until line break
+                Err(error)
+                    if error.kind() == ErrorKind::WouldBlock
+                        || error.kind() == ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    // public function
+    pub fn bind(config: ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(&config.bind_address)?;
+        Ok(TcpServer { listener, config })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // To accept connections until shutdown flag is set.
+    // Storage is restored before first connection is accepted.
+    // After shutdown, server stops accepting, waits for open connections
+    // to finish current query and saves snapshot.
+    pub fn run(&self, shutdown: Arc<AtomicBool>) -> io::Result<()> {
+        self.listener.set_nonblocking(true)?;
+
+        let concurrent_channel = ConcurrentChannel::open(self.config.storage.clone())
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        let concurrent_channel = Arc::new(concurrent_channel);
+        let active_connections = Arc::new(AtomicUsize::new(0));
+        let mut connections: Vec<JoinHandle<()>> = Vec::new();
+
+        while !shutdown.load(Ordering::SeqCst) {
+            match self.listener.accept() {
+                Ok((mut stream, _)) => {
+                    connections.retain(|connection| !connection.is_finished());
+
+                    if active_connections.load(Ordering::SeqCst) >= self.config.max_connections {
+                        let response = wire_format::encode_error("busy", "max connections reached");
+                        let _ = stream.write_all(response.as_bytes());
+                        continue;
+                    }
+
+                    let connection_slot = ConnectionSlot::take(&active_connections);
+                    let concurrent_channel = concurrent_channel.clone();
+                    let shutdown = shutdown.clone();
+                    let max_line_length = self.config.max_line_length;
+                    connections.push(thread::spawn(move || {
+                        let _connection_slot = connection_slot;
+                        let result = Self::handle_connection(stream, concurrent_channel, shutdown, max_line_length);
+                        if let Err(error) = result {
+                            eprintln!("qdb: connection error: {}", error);
+                        }
+                    }));
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL);
+                }
+                // accept error of one client must not stop server
+                Err(error) => eprintln!("qdb: accept error: {}", error),
+            }
+        }
+
+        for connection in connections {
+            let _ = connection.join();
+        }
+        if let Err(error) = concurrent_channel.checkpoint() {
+            eprintln!("qdb: checkpoint error: {}", error);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::server::tcp_server::{ConnectionSlot, ServerConfig, TcpServer};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_connection_slot() {
+        let active_connections = Arc::new(AtomicUsize::new(0));
+        let connection_slot = ConnectionSlot::take(&active_connections);
+        debug_assert_eq!(1, active_connections.load(Ordering::SeqCst));
+
+        // slot of panicked connection thread is released
+        let result = thread::spawn(move || {
+            let _connection_slot = connection_slot;
+            panic!("connection thread panics");
+        })
+        .join();
+        debug_assert!(result.is_err());
+        debug_assert_eq!(0, active_connections.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_tcp_server() {
+        let config = ServerConfig {
+            bind_address: "127.0.0.1:0".to_string(),
+            max_connections: 1,
+            ..ServerConfig::default()
+        };
+        let server = TcpServer::bind(config).unwrap();
+        let address = server.local_addr().unwrap();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let server_shutdown = shutdown.clone();
+        let server_thread = thread::spawn(move || server.run(server_shutdown));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+
+        stream.write_all(b"onCreate(my_node)(c:int = 2)\n").unwrap();
+        reader.read_line(&mut line).unwrap();
+        debug_assert_eq!("OK\n", line);
+
+        line.clear();
+        stream.write_all(b"onRead(my_node)(c > 0)\r\n").unwrap();
+        reader.read_line(&mut line).unwrap();
+        reader.read_line(&mut line).unwrap();
+        reader.read_line(&mut line).unwrap();
+        debug_assert_eq!("OK 1\nc\n0\t0\tint:2\n", line);
+
+        // second connection is over the limit
+        let second_stream = TcpStream::connect(address).unwrap();
+        let mut second_reader = BufReader::new(second_stream);
+        line.clear();
+        second_reader.read_line(&mut line).unwrap();
+        debug_assert_eq!("ERR busy max connections reached\n", line);
+
+        shutdown.store(true, Ordering::SeqCst);
+        server_thread.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_tcp_server_max_line_length() {
+        let config = ServerConfig {
+            bind_address: "127.0.0.1:0".to_string(),
+            max_line_length: 32,
+            ..ServerConfig::default()
+        };
+        let server = TcpServer::bind(config).unwrap();
+        let address = server.local_addr().unwrap();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let server_shutdown = shutdown.clone();
+        let server_thread = thread::spawn(move || server.run(server_shutdown));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+
+        stream.write_all(b"onCreate(my_node)(c:int = 2)\n").unwrap();
+        reader.read_line(&mut line).unwrap();
+        debug_assert_eq!("OK\n", line);
+
+        // connection is closed after error of too long line, line break isn't waited for
+        line.clear();
+        stream.write_all(b"onRead(my_node)(c > 0 and c < 9)").unwrap();
+        reader.read_line(&mut line).unwrap();
+        debug_assert_eq!("ERR line_too_long query line is longer than 32 bytes\n", line);
+        line.clear();
+        debug_assert_eq!(0, reader.read_line(&mut line).unwrap());
+
+        shutdown.store(true, Ordering::SeqCst);
+        server_thread.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use crate::memory::result_set::ResultSet;
+use crate::memory::subscription::Notification;
+use crate::error::query_error::QueryError;
+use crate::query::query_resolver::QueryResponse;
+use qdb_ast::ast::types::DataType;
+
+// Wire format of qdb server.
+// Request - one query per line, UTF-8, terminated by \n (\r\n is accepted).
+// Response - for each request one of:
+//     OK
+//     OK <count>
+//     <column>\t<column>...                (header)
+//     <from>\t<to>\t<value>\t<value>...    (count lines)
+//     ERR <kind> <message>
+// OK - query is applied and has no result (onCreate, onUpdate, onDelete).
+// OK <count> - query has result of count rows. Header line has names of columns,
+// each row is one line with range of logic time and one value per column,
+// separated by tab.
+// onChange is answered by SUBSCRIBED <id>, then every written row where its condition
+// is true is pushed to connection, between responses, as
+//     CHANGE <id> <channel>
+//     <column>\t<column>...
+//     <from>\t<to>\t<value>\t<value>...
+// Subscription lives until connection is closed.
+// ERR - query is rejected, where kind is one of parse_error, unknown_channel,
+// unknown_variable, ambiguous_variable, unsupported_operator, type_mismatch,
+// storage_error, busy.
+// Values are encoded as null, int:<number>, real:<number>, text:"<text>", symbol:<name>;
+// inside text \, ", tab and line breaks are escaped as \\, \", \t, \n, \r.
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for symbol in text.chars() {
+        match symbol {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(symbol),
+        }
+    }
+    escaped
+}
+
+fn get_error_kind(query_error: &QueryError) -> &'static str {
+    match query_error {
+        QueryError::ParseError(_) => "parse_error",
+        QueryError::UnknownChannel(_) => "unknown_channel",
+        QueryError::UnknownVariable(_) => "unknown_variable",
+        QueryError::AmbiguousVariable(_) => "ambiguous_variable",
+        QueryError::UnsupportedOperator(_) => "unsupported_operator",
+        QueryError::TypeMismatch(_) => "type_mismatch",
+        QueryError::StorageError(_) => "storage_error",
+    }
+}
+
+pub fn encode_value(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "null".to_string(),
+        DataType::Int(value) => format!("int:{}", value),
+        DataType::Real(value) => format!("real:{}", value),
+        DataType::Text(value) => format!("text:\"{}\"", escape_text(value)),
+        DataType::Symbol(value) => format!("symbol:{}", value),
+    }
+}
+
+pub fn encode_result_set(result_set: &ResultSet) -> String {
+    let response = format!("OK {}\n", result_set.get_rows().len());
+    encode_rows(response, result_set)
+}
+
+// To append header and rows of result set to response
+fn encode_rows(mut response: String, result_set: &ResultSet) -> String {
+    response.push_str(&result_set.get_columns().join("\t"));
+    response.push('\n');
+    for row in result_set.get_rows() {
+        let logic_times = row.get_logic_times();
+        response.push_str(&format!("{}\t{}", logic_times.start(), logic_times.end()));
+        for value in row.get_values() {
+            response.push('\t');
+            response.push_str(&encode_value(value));
+        }
+        response.push('\n');
+    }
+    response
+}
+
+pub fn encode_error(kind: &str, message: &str) -> String {
+    format!("ERR {} {}\n", kind, escape_text(message))
+}
+
+pub fn encode_notification(notification: &Notification) -> String {
+    let response = format!(
+        "CHANGE {} {}\n",
+        notification.get_subscription_id(),
+        notification.get_channel_name()
+    );
+    encode_rows(response, &notification.to_result_set())
+}
+
+// To encode result of query resolving, response always ends with line break
+pub fn encode_response(result: &Result<QueryResponse, QueryError>) -> String {
+    match result {
+        Ok(QueryResponse::None) => "OK\n".to_string(),
+        Ok(QueryResponse::ResultSet(result_set)) => encode_result_set(result_set),
+        Ok(QueryResponse::Subscription(subscriber)) => format!("SUBSCRIBED {}\n", subscriber.get_id()),
+        Err(query_error) => encode_error(get_error_kind(query_error), &query_error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_channel::MemoryChannel;
+    use crate::memory::result_set::{ResultSet, Row};
+    use crate::error::query_error::QueryError;
+    use crate::query::query_resolver::{QueryResolver, QueryResponse};
+    use crate::server::wire_format::{encode_notification, encode_response};
+    use qdb_ast::ast::types::DataType;
+
+    #[test]
+    fn test_wire_format_encode_response() {
+        debug_assert_eq!("OK\n", encode_response(&Ok(QueryResponse::None)));
+
+        let mut result_set = ResultSet::new(vec!["a".to_string(), "b".to_string()]);
+        result_set.push_row(Row::new(0..=1, vec![DataType::Int(1), DataType::Null]));
+        result_set.push_row(Row::new(2..=2, vec![DataType::Null, DataType::Text("x\ty".to_string())]));
+        debug_assert_eq!(
+            "OK 2\na\tb\n0\t1\tint:1\tnull\n2\t2\tnull\ttext:\"x\\ty\"\n",
+            encode_response(&Ok(QueryResponse::ResultSet(result_set)))
+        );
+
+        debug_assert_eq!(
+            "ERR unknown_channel unknown channel: my_node\n",
+            encode_response(&Err(QueryError::UnknownChannel("my_node".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_wire_format_encode_notification() {
+        let mut a = MemoryChannel::new();
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 0)".to_string()).unwrap();
+        let response = QueryResolver::resolve(&mut a, "onChange(my_node)(c > 0)".to_string());
+        let subscriber = match &response {
+            Ok(QueryResponse::Subscription(subscriber)) => subscriber,
+            _ => panic!("onChange must return subscriber"),
+        };
+        debug_assert_eq!(format!("SUBSCRIBED {}\n", subscriber.get_id()), encode_response(&response));
+
+        QueryResolver::resolve(&mut a, "onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        let notification = subscriber.get_receiver().try_recv().unwrap();
+        debug_assert_eq!(
+            format!("CHANGE {} my_node\nc\n1\t1\tint:2\n", subscriber.get_id()),
+            encode_notification(&notification)
+        );
+    }
+}
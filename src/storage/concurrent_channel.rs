@@ -0,0 +1,448 @@
+use crate::memory::memory_channel::{ChannelAccess, MemoryChannel};
+use crate::memory::memory_table::MemoryTable;
+use crate::error::query_error::QueryError;
+use crate::query::join_clause::JoinClause;
+use crate::query::query_resolver::{QueryResolver, QueryResponse};
+use crate::query::query_scanner::{find_groups, split_top_level};
+use crate::query::read_clause::ReadClause;
+use crate::query::transaction::Transaction;
+use crate::storage::durable_channel::{DurableChannel, StorageConfig};
+use crate::storage::snapshot;
+use crate::storage::write_ahead_log::{WalRecord, WriteAheadLog};
+use rbtree::RBTree;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+type SharedTable = Arc<RwLock<MemoryTable>>;
+
+// How query locks its channels
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write,
+    // write which may create unknown channels
+    Create,
+}
+
+enum TableGuard<'a> {
+    Read(RwLockReadGuard<'a, MemoryTable>),
+    Write(RwLockWriteGuard<'a, MemoryTable>),
+}
+
+// Tables locked for one query, tables created by query are kept here
+// until query is resolved and then added to channel
+struct LockedChannels<'a> {
+    tables: BTreeMap<String, TableGuard<'a>>,
+    created: BTreeMap<String, MemoryTable>,
+    is_create_allowed: bool,
+}
+
+impl<'a> ChannelAccess for LockedChannels<'a> {
+    fn get_table(&self, channel_name: &String) -> Option<&MemoryTable> {
+        match self.tables.get(channel_name) {
+            Some(TableGuard::Read(guard)) => Some(guard),
+            Some(TableGuard::Write(guard)) => Some(guard),
+            None => self.created.get(channel_name),
+        }
+    }
+
+    fn get_table_mut(&mut self, channel_name: &String) -> Option<&mut MemoryTable> {
+        match self.tables.get_mut(channel_name) {
+            Some(TableGuard::Read(_)) => None,
+            Some(TableGuard::Write(guard)) => Some(guard),
+            None => self.created.get_mut(channel_name),
+        }
+    }
+
+    fn get_or_create_table(&mut self, channel_name: &String) -> Result<&mut MemoryTable, QueryError> {
+        let is_known = self.tables.contains_key(channel_name) || self.created.contains_key(channel_name);
+        if !is_known && self.is_create_allowed {
+            self.created.insert(channel_name.to_string(), MemoryTable::init());
+        }
+        self.get_table_mut(channel_name)
+            .ok_or_else(|| QueryError::UnknownChannel(channel_name.to_string()))
+    }
+
+    fn get_tables(&self) -> Vec<(&String, &MemoryTable)> {
+        let mut tables: Vec<(&String, &MemoryTable)> = self
+            .tables
+            .keys()
+            .chain(self.created.keys())
+            .filter_map(|channel_name| Some((channel_name, self.get_table(channel_name)?)))
+            .collect();
+        tables.sort_by_key(|(channel_name, _)| *channel_name);
+        tables
+    }
+}
+
+// MemoryChannel shared by threads, every channel has its own read-write lock.
+// Query locks all its channels before it's resolved, in order of channel names,
+// so queries never wait for each other in cycle:
+// - reads of any channels run in parallel, also reads of one channel;
+// - write locks only its channels, so queries of other channels aren't blocked;
+// - query which creates channel also locks list of channels.
+// Consistency: query sees each of its channels between whole queries,
+// read racing with write of channel sees channel before or after write, never part of it,
+// and read of several channels sees them at one moment.
+// Writes of one channel are logged and applied in order of their locks,
+// so log replay gives the same state.
+pub struct ConcurrentChannel {
+    channels: RwLock<RBTree<String, SharedTable>>,
+    wal: Option<Mutex<WriteAheadLog>>,
+    config: StorageConfig,
+}
+
+impl ConcurrentChannel {
+    // hidden function
+    // Query kind and channel names are taken from text before query is parsed,
+    // by the same splits as resolver uses: <function>(<channel>, ...)...; None means all channels.
+    // Join locks both its channels, transaction locks channels of all its statements
+    // or all channels if any statement needs them (e.g. channels()).
+    // Malformed query locks nothing and fails in resolver.
+    fn get_access(line: &str) -> (Access, Option<Vec<String>>) {
+        if let Some(Ok(transaction)) = Transaction::parse(line) {
+            let mut channel_names: Option<Vec<String>> = Some(Vec::new());
+            for statement in transaction.get_statements() {
+                match (Self::get_access(statement).1, channel_names.as_mut()) {
+                    (Some(statement_names), Some(channel_names)) => channel_names.extend(statement_names),
+                    _ => channel_names = None,
+                }
+            }
+            return (Access::Create, channel_names);
+        }
+        let line = line.trim();
+        let name = line.split('(').next().unwrap_or("").trim();
+        let mut channel_names: Vec<String> = match find_groups(line).first() {
+            Some((start, end)) => split_top_level(&line[*start..*end], ',')
+                .into_iter()
+                .filter(|channel_name| !channel_name.is_empty())
+                .map(str::to_string)
+                .collect(),
+            None => return (Access::Read, Some(Vec::new())),
+        };
+        if name == "onRead" {
+            let join_clause = ReadClause::split(line).and_then(|(line, _)| JoinClause::split(&line));
+            if let Ok((_, join_clause)) = join_clause {
+                if let Some((left, right)) = join_clause.get_channel_names() {
+                    channel_names.push(left.to_string());
+                    channel_names.push(right.to_string());
+                }
+            }
+        }
+        match name {
+            "channels" => (Access::Read, None),
+            "describe" | "onRead" => (Access::Read, Some(channel_names)),
+            "onCreate" | "alter" => (Access::Create, Some(channel_names)),
+            _ => (Access::Write, Some(channel_names)),
+        }
+    }
+
+    // lock of panicked query is taken over, table keeps state of panic
+    fn read_table(table: &SharedTable) -> RwLockReadGuard<'_, MemoryTable> {
+        table.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write_table(table: &SharedTable) -> RwLockWriteGuard<'_, MemoryTable> {
+        table.write().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn lock_wal(wal: &Mutex<WriteAheadLog>) -> MutexGuard<'_, WriteAheadLog> {
+        wal.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    // To get shared tables of channels in order of names, unknown channels are skipped
+    fn get_shared_tables(
+        channels: &RBTree<String, SharedTable>,
+        channel_names: Option<Vec<String>>,
+    ) -> Vec<(String, SharedTable)> {
+        let mut shared_tables: Vec<(String, SharedTable)> = match channel_names {
+            Some(channel_names) => channel_names
+                .into_iter()
+                .filter_map(|channel_name| {
+                    let table = channels.get(&channel_name)?.clone();
+                    Some((channel_name, table))
+                })
+                .collect(),
+            None => channels
+                .iter()
+                .map(|(channel_name, table)| (channel_name.to_string(), table.clone()))
+                .collect(),
+        };
+        shared_tables.sort_by(|(left, _), (right, _)| left.cmp(right));
+        shared_tables.dedup_by(|(left, _), (right, _)| left == right);
+        shared_tables
+    }
+
+    fn lock_tables(shared_tables: &[(String, SharedTable)], access: Access) -> LockedChannels<'_> {
+        let tables = shared_tables
+            .iter()
+            .map(|(channel_name, table)| {
+                let guard = match access {
+                    Access::Read => TableGuard::Read(Self::read_table(table)),
+                    Access::Write | Access::Create => TableGuard::Write(Self::write_table(table)),
+                };
+                (channel_name.to_string(), guard)
+            })
+            .collect();
+        LockedChannels {
+            tables,
+            created: BTreeMap::new(),
+            is_create_allowed: access == Access::Create,
+        }
+    }
+
+    fn resolve_locked(
+        &self,
+        locked_channels: &mut LockedChannels,
+        line: String,
+    ) -> Result<QueryResponse, QueryError> {
+        match self.wal.as_ref() {
+            Some(wal) => {
+                let mut append_log = |record: &WalRecord| Self::lock_wal(wal).append(record);
+                QueryResolver::resolve_channels(locked_channels, Some(&mut append_log), line)
+            }
+            None => QueryResolver::resolve_channels(locked_channels, None, line),
+        }
+    }
+
+    // public function
+    pub fn new(mem_channel: MemoryChannel) -> Self {
+        Self::init(mem_channel, None, StorageConfig::default())
+    }
+
+    pub fn init(mem_channel: MemoryChannel, wal: Option<WriteAheadLog>, config: StorageConfig) -> Self {
+        let mut channels = RBTree::new();
+        for (channel_name, mem_table) in mem_channel {
+            channels.insert(channel_name, Arc::new(RwLock::new(mem_table)));
+        }
+        ConcurrentChannel {
+            channels: RwLock::new(channels),
+            wal: wal.map(Mutex::new),
+            config,
+        }
+    }
+
+    // To restore channel from snapshot and log
+    pub fn open(config: StorageConfig) -> Result<Self, QueryError> {
+        Ok(DurableChannel::open(config)?.into_concurrent())
+    }
+
+    // Query which creates channels holds write lock of list of channels,
+    // so channels are created by one query at once
+    pub fn resolve(&self, line: String) -> Result<QueryResponse, QueryError> {
+        let (access, channel_names) = Self::get_access(&line);
+        let channels = self.channels.read().unwrap_or_else(|error| error.into_inner());
+        let is_creating = access == Access::Create
+            && channel_names
+                .iter()
+                .flatten()
+                .any(|channel_name| !channels.contains_key(channel_name));
+        if !is_creating {
+            let shared_tables = Self::get_shared_tables(&channels, channel_names);
+            drop(channels);
+            let mut locked_channels = Self::lock_tables(&shared_tables, access);
+            return self.resolve_locked(&mut locked_channels, line);
+        }
+        drop(channels);
+
+        let mut channels = self.channels.write().unwrap_or_else(|error| error.into_inner());
+        let shared_tables = Self::get_shared_tables(&channels, channel_names);
+        let mut locked_channels = Self::lock_tables(&shared_tables, access);
+        let result = self.resolve_locked(&mut locked_channels, line);
+        // channels are created even by failed query, as in MemoryChannel
+        for (channel_name, mem_table) in std::mem::take(&mut locked_channels.created) {
+            channels.insert(channel_name, Arc::new(RwLock::new(mem_table)));
+        }
+        result
+    }
+
+    // To get logic time of channel, 0 for unknown channel
+    pub fn get_logic_time(&self, channel_name: &String) -> i64 {
+        let channels = self.channels.read().unwrap_or_else(|error| error.into_inner());
+        channels
+            .get(channel_name)
+            .map(|table| Self::read_table(table).get_logic_time())
+            .unwrap_or(0)
+    }
+
+    // To check that log is big enough to be saved to snapshot
+    pub fn is_checkpoint_needed(&self) -> bool {
+        match (self.wal.as_ref(), self.config.snapshot_path.as_ref()) {
+            (Some(wal), Some(_)) => Self::lock_wal(wal).get_records_count() >= self.config.checkpoint_records,
+            _ => false,
+        }
+    }
+
+    // To save snapshot and start next generation of log.
+    // All channels are locked for read, so snapshot and log are cut between writes.
+    pub fn checkpoint(&self) -> Result<(), QueryError> {
+        let snapshot_path = match self.config.snapshot_path.as_ref() {
+            Some(snapshot_path) => snapshot_path,
+            None => return Ok(()),
+        };
+        let channels = self.channels.read().unwrap_or_else(|error| error.into_inner());
+        let shared_tables = Self::get_shared_tables(&channels, None);
+        let locked_channels = Self::lock_tables(&shared_tables, Access::Read);
+        match self.wal.as_ref() {
+            Some(wal) => {
+                let mut wal = Self::lock_wal(wal);
+                let generation = wal.get_generation() + 1;
+                let bytes = snapshot::encode_channels(&locked_channels, generation);
+                snapshot::write_snapshot_file(snapshot_path, &bytes)?;
+                wal.reset(generation)
+            }
+            None => {
+                let bytes = snapshot::encode_channels(&locked_channels, 0);
+                snapshot::write_snapshot_file(snapshot_path, &bytes)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_channel::MemoryChannel;
+    use crate::error::query_error::QueryError;
+    use crate::query::query_resolver::QueryResponse;
+    use crate::storage::concurrent_channel::{Access, ConcurrentChannel};
+    use crate::storage::durable_channel::StorageConfig;
+    use std::fs;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn read_count(concurrent_channel: &ConcurrentChannel, line: &str) -> usize {
+        match concurrent_channel.resolve(line.to_string()) {
+            Ok(QueryResponse::ResultSet(result)) => result.get_rows().len(),
+            _ => panic!("onRead must return result set"),
+        }
+    }
+
+    #[test]
+    fn test_concurrent_channel_resolve() {
+        let concurrent_channel = ConcurrentChannel::new(MemoryChannel::new());
+        concurrent_channel.resolve("onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        concurrent_channel.resolve("alter(other_node)(d:int)".to_string()).unwrap();
+        concurrent_channel.resolve("onCreate(other_node)(d:int = 2)".to_string()).unwrap();
+
+        debug_assert_eq!(1, read_count(&concurrent_channel, "onRead(my_node)(c > 0)"));
+        debug_assert_eq!(2, read_count(&concurrent_channel, "onRead(my_node, other_node)() between 0 and 0"));
+        debug_assert_eq!(2, read_count(&concurrent_channel, "channels()"));
+        debug_assert_eq!(
+            Some(QueryError::UnknownChannel("new_node".to_string())),
+            concurrent_channel.resolve("onUpdate(new_node)(c == 1)(c = 2)".to_string()).err()
+        );
+        debug_assert!(matches!(
+            concurrent_channel.resolve("alter(new_node)(c)".to_string()),
+            Err(QueryError::ParseError(_))
+        ));
+        debug_assert_eq!(2, read_count(&concurrent_channel, "channels()"));
+
+        concurrent_channel
+            .resolve("transaction(onCreate(my_node)(c:int = 2); onCreate(new_node)(c:int = 1))".to_string())
+            .unwrap();
+        debug_assert_eq!(2, concurrent_channel.get_logic_time(&"my_node".to_string()));
+        debug_assert_eq!(1, concurrent_channel.get_logic_time(&"new_node".to_string()));
+    }
+
+    #[test]
+    fn test_concurrent_channel_join() {
+        let concurrent_channel = ConcurrentChannel::new(MemoryChannel::new());
+        for line in &[
+            "onCreate(orders)(user_id:int = 1, amount:int = 10)",
+            "onCreate(orders)(user_id:int = 2, amount:int = 20)",
+            "onCreate(users)(id:int = 2)",
+        ] {
+            concurrent_channel.resolve(line.to_string()).unwrap();
+        }
+        assert_eq!(1, read_count(&concurrent_channel, "onRead(orders, users)(orders.user_id == users.id)"));
+
+        // lock set is taken from the same splits as resolver uses:
+        // channel out of list of query isn't joined, as in MemoryChannel
+        assert_eq!(
+            (Access::Read, Some(vec!["orders".to_string()])),
+            ConcurrentChannel::get_access("onRead(orders)(orders.user_id == users.id)")
+        );
+        assert_eq!(
+            Some(QueryError::UnknownChannel("users".to_string())),
+            concurrent_channel.resolve("onRead(orders)(orders.user_id == users.id)".to_string()).err()
+        );
+        assert_eq!(
+            (Access::Create, None),
+            ConcurrentChannel::get_access("transaction(onCreate(orders)(amount:int = 1); channels())")
+        );
+        assert_eq!(
+            (Access::Create, Some(vec!["orders".to_string(), "users".to_string()])),
+            ConcurrentChannel::get_access("transaction(onCreate(orders)(amount:int = 1); onDelete(users)(id == 1))")
+        );
+    }
+
+    #[test]
+    fn test_concurrent_channel_threads() {
+        let concurrent_channel = Arc::new(ConcurrentChannel::new(MemoryChannel::new()));
+        let writers: Vec<_> = ["my_node", "other_node"]
+            .iter()
+            .map(|channel_name| {
+                let concurrent_channel = concurrent_channel.clone();
+                thread::spawn(move || {
+                    for c in 0..100 {
+                        let line = format!("onCreate({})(c:int = {}, d:int = {})", channel_name, c, c);
+                        concurrent_channel.resolve(line).unwrap();
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let concurrent_channel = concurrent_channel.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        // row is written at once, so read never sees c without d
+                        let response = concurrent_channel.resolve("onRead(my_node)(c >= 0)".to_string());
+                        if let Ok(QueryResponse::ResultSet(result)) = response {
+                            for row in result.get_rows() {
+                                debug_assert_eq!(row.get_values()[0], row.get_values()[1]);
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in writers.into_iter().chain(readers) {
+            thread.join().unwrap();
+        }
+
+        debug_assert_eq!(100, concurrent_channel.get_logic_time(&"my_node".to_string()));
+        debug_assert_eq!(100, concurrent_channel.get_logic_time(&"other_node".to_string()));
+        debug_assert_eq!(100, read_count(&concurrent_channel, "onRead(my_node)(d >= 0)"));
+    }
+
+    #[test]
+    fn test_concurrent_channel_checkpoint() {
+        let directory = std::env::temp_dir().join("qdb_concurrent_channel");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let config = StorageConfig {
+            wal_path: Some(directory.join("qdb.wal")),
+            snapshot_path: Some(directory.join("qdb.snapshot")),
+            checkpoint_records: 2,
+            ..StorageConfig::default()
+        };
+
+        let concurrent_channel = ConcurrentChannel::open(config.clone()).unwrap();
+        concurrent_channel.resolve("onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        concurrent_channel.resolve("onCreate(other_node)(c:int = 1)".to_string()).unwrap();
+        debug_assert!(concurrent_channel.is_checkpoint_needed());
+        concurrent_channel.checkpoint().unwrap();
+        debug_assert!(!concurrent_channel.is_checkpoint_needed());
+        concurrent_channel.resolve("onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        drop(concurrent_channel);
+
+        // snapshot and log after it
+        let concurrent_channel = ConcurrentChannel::open(config).unwrap();
+        debug_assert_eq!(2, concurrent_channel.get_logic_time(&"my_node".to_string()));
+        debug_assert_eq!(1, concurrent_channel.get_logic_time(&"other_node".to_string()));
+        drop(concurrent_channel);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use crate::memory::memory_channel::MemoryChannel;
+use crate::error::query_error::QueryError;
+use crate::query::query_resolver::{QueryResolver, QueryResponse};
+use crate::storage::concurrent_channel::ConcurrentChannel;
+use crate::storage::snapshot::Snapshot;
+use crate::storage::write_ahead_log::{SyncPolicy, WriteAheadLog};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    // without log and snapshot all data is lost on restart
+    pub wal_path: Option<PathBuf>,
+    pub snapshot_path: Option<PathBuf>,
+    pub sync_policy: SyncPolicy,
+    // count of log records after which log is saved to snapshot
+    pub checkpoint_records: usize,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            wal_path: None,
+            snapshot_path: None,
+            sync_policy: SyncPolicy::Always,
+            checkpoint_records: 10_000,
+        }
+    }
+}
+
+// MemoryChannel restored from snapshot and log written after it.
+// Checkpoint saves snapshot with next generation of log and then resets log,
+// so crash between them keeps log which is skipped on restore.
+pub struct DurableChannel {
+    mem_channel: MemoryChannel,
+    wal: Option<WriteAheadLog>,
+    config: StorageConfig,
+}
+
+impl DurableChannel {
+    // public function
+    pub fn open(config: StorageConfig) -> Result<Self, QueryError> {
+        let (mut mem_channel, generation) = match config.snapshot_path.as_ref() {
+            Some(snapshot_path) if snapshot_path.exists() => MemoryChannel::load_snapshot(snapshot_path)?,
+            _ => (MemoryChannel::new(), 0),
+        };
+
+        let mut wal = None;
+        if let Some(wal_path) = config.wal_path.as_ref() {
+            WriteAheadLog::replay(wal_path, &mut mem_channel, generation)?;
+            let mut opened = WriteAheadLog::open(wal_path, config.sync_policy)?;
+            if opened.get_generation() < generation {
+                opened.reset(generation)?;
+            }
+            wal = Some(opened);
+        }
+
+        Ok(DurableChannel {
+            mem_channel,
+            wal,
+            config,
+        })
+    }
+
+    pub fn resolve(&mut self, line: String) -> Result<QueryResponse, QueryError> {
+        match self.wal.as_mut() {
+            Some(wal) => QueryResolver::resolve_with_log(&mut self.mem_channel, wal, line),
+            None => QueryResolver::resolve(&mut self.mem_channel, line),
+        }
+    }
+
+    pub fn get_mem_channel(&self) -> &MemoryChannel {
+        &self.mem_channel
+    }
+
+    // To share restored channel and its log between threads
+    pub fn into_concurrent(self) -> ConcurrentChannel {
+        ConcurrentChannel::init(self.mem_channel, self.wal, self.config)
+    }
+
+    // To check that log is big enough to be saved to snapshot
+    pub fn is_checkpoint_needed(&self) -> bool {
+        match (self.wal.as_ref(), self.config.snapshot_path.as_ref()) {
+            (Some(wal), Some(_)) => wal.get_records_count() >= self.config.checkpoint_records,
+            _ => false,
+        }
+    }
+
+    // To save snapshot and start next generation of log
+    pub fn checkpoint(&mut self) -> Result<(), QueryError> {
+        let snapshot_path = match self.config.snapshot_path.as_ref() {
+            Some(snapshot_path) => snapshot_path,
+            None => return Ok(()),
+        };
+        match self.wal.as_mut() {
+            Some(wal) => {
+                let generation = wal.get_generation() + 1;
+                self.mem_channel.save_snapshot(snapshot_path, generation)?;
+                wal.reset(generation)
+            }
+            None => self.mem_channel.save_snapshot(snapshot_path, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::result_set::ResultSet;
+    use crate::query::query_resolver::QueryResponse;
+    use crate::storage::durable_channel::{DurableChannel, StorageConfig};
+    use std::fs;
+
+    fn read(durable_channel: &mut DurableChannel) -> ResultSet {
+        match durable_channel.resolve("onRead(my_node)() between 0 and 10".to_string()) {
+            Ok(QueryResponse::ResultSet(result)) => result,
+            _ => panic!("onRead must return result set"),
+        }
+    }
+
+    #[test]
+    fn test_durable_channel() {
+        let directory = std::env::temp_dir().join("qdb_durable_channel");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let config = StorageConfig {
+            wal_path: Some(directory.join("qdb.wal")),
+            snapshot_path: Some(directory.join("qdb.snapshot")),
+            checkpoint_records: 2,
+            ..StorageConfig::default()
+        };
+
+        let mut durable_channel = DurableChannel::open(config.clone()).unwrap();
+        durable_channel.resolve("onCreate(my_node)(c:int = 1)".to_string()).unwrap();
+        debug_assert!(!durable_channel.is_checkpoint_needed());
+        durable_channel.resolve("onCreate(my_node)(c:int = 2)".to_string()).unwrap();
+        debug_assert!(durable_channel.is_checkpoint_needed());
+        durable_channel.checkpoint().unwrap();
+        durable_channel.resolve("onCreate(my_node)(c:int = 3)".to_string()).unwrap();
+        let expected = read(&mut durable_channel);
+        drop(durable_channel);
+
+        // snapshot and log after it
+        let mut durable_channel = DurableChannel::open(config.clone()).unwrap();
+        debug_assert_eq!(expected, read(&mut durable_channel));
+        debug_assert_eq!(
+            3,
+            durable_channel.get_mem_channel().get(&"my_node".to_string()).unwrap().get_logic_time()
+        );
+        drop(durable_channel);
+
+        // crash after snapshot is saved, but before log is reset
+        let log = fs::read_to_string(directory.join("qdb.wal")).unwrap();
+        let mut durable_channel = DurableChannel::open(config.clone()).unwrap();
+        durable_channel.checkpoint().unwrap();
+        drop(durable_channel);
+        fs::write(directory.join("qdb.wal"), log).unwrap();
+        let mut durable_channel = DurableChannel::open(config).unwrap();
+        debug_assert_eq!(expected, read(&mut durable_channel));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+pub mod concurrent_channel;
+pub mod durable_channel;
+pub mod snapshot;
+pub mod write_ahead_log;
@@ -0,0 +1,504 @@
+use crate::memory::memory_channel::{ChannelAccess, MemoryChannel};
+use crate::memory::memory_machine::{IntervalSet, MemoryMachine};
+use crate::memory::memory_table::MemoryTable;
+use crate::memory::retention_policy::RetentionPolicy;
+use crate::memory::schema::{Schema, ValueType};
+use crate::error::query_error::QueryError;
+use qdb_ast::ast::types::DataType;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+// Binary snapshot of MemoryChannel, integers are little endian:
+//     magic "QDBS" | version u32 | body | crc32 of body u32
+// Body of version 3:
+//     wal generation u64 | channels count u64 | (name, table)...
+//     table   - logic time i64 | variables count u64 | (name, machine)... | schema | retention
+//     machine - logic time i64 | last value option | values count u64 | (value, interval set)...
+//               | timeline count u64 | (logic time i64, value)... | deleted interval set
+//     string  - length u64 | UTF-8 bytes
+//     option  - 0 | 1 value
+//     value   - tag u8 (0 null, 1 int, 2 real, 3 text, 4 symbol) | i64, f64 bits or string
+//     interval set - ranges count u64 | (start i64, end i64)...
+//     schema  - variables count u64 | (name, type tag u8 (0 untyped, 1 int, 2 real, 3 text, 4 symbol))...
+//     retention - tag u8 (0 none, 1 ticks i64, 2 values u64, 3 watermark i64)
+// Version 1 has no schema of table, version 2 has no retention of table.
+// Reader knows version of snapshot, so fields of new versions are read
+// only from new snapshots and old snapshots stay loadable.
+
+const MAGIC: &[u8; 4] = b"QDBS";
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+// CRC-32 (IEEE), bitwise
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[derive(Default)]
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    // public function
+    pub fn new() -> Self {
+        SnapshotWriter { bytes: Vec::new() }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    pub fn write_data_type(&mut self, data_type: &DataType) {
+        match data_type {
+            DataType::Null => self.write_u8(0),
+            DataType::Int(value) => {
+                self.write_u8(1);
+                self.write_i64(*value);
+            }
+            DataType::Real(value) => {
+                self.write_u8(2);
+                self.write_u64(value.to_bits());
+            }
+            DataType::Text(value) => {
+                self.write_u8(3);
+                self.write_str(value);
+            }
+            DataType::Symbol(value) => {
+                self.write_u8(4);
+                self.write_str(value);
+            }
+        }
+    }
+
+    pub fn write_option_data_type(&mut self, data_type: Option<&DataType>) {
+        match data_type {
+            Some(data_type) => {
+                self.write_u8(1);
+                self.write_data_type(data_type);
+            }
+            None => self.write_u8(0),
+        }
+    }
+
+    pub fn write_schema(&mut self, schema: &Schema) {
+        self.write_u64(schema.iter().count() as u64);
+        for (name, value_type) in schema.iter() {
+            self.write_str(name);
+            self.write_u8(match value_type {
+                None => 0,
+                Some(ValueType::Int) => 1,
+                Some(ValueType::Real) => 2,
+                Some(ValueType::Text) => 3,
+                Some(ValueType::Symbol) => 4,
+            });
+        }
+    }
+
+    pub fn write_interval_set(&mut self, interval_set: &IntervalSet) {
+        self.write_u64(interval_set.ranges_count() as u64);
+        for range in interval_set {
+            self.write_i64(*range.start());
+            self.write_i64(*range.end());
+        }
+    }
+
+    // To write full state of machine
+    pub fn write_mem_machine(&mut self, mem_machine: &MemoryMachine) {
+        self.write_i64(mem_machine.get_logic_time());
+        self.write_option_data_type(mem_machine.get_last_inserted());
+        let values: Vec<(&DataType, &IntervalSet)> = mem_machine.get_values().collect();
+        self.write_u64(values.len() as u64);
+        for (data_type, indexes) in values {
+            self.write_data_type(data_type);
+            self.write_interval_set(indexes);
+        }
+        self.write_u64(mem_machine.get_ranges_count() as u64);
+        for (logic_time, data_type) in mem_machine.get_timeline() {
+            self.write_i64(logic_time);
+            self.write_data_type(data_type);
+        }
+        self.write_interval_set(mem_machine.get_deleted());
+    }
+
+    // To write full state of table, variables in alphabetical order
+    pub fn write_mem_table(&mut self, mem_table: &MemoryTable) {
+        self.write_i64(mem_table.get_logic_time());
+        let names = mem_table.get_var_names();
+        self.write_u64(names.len() as u64);
+        for name in names {
+            self.write_str(name);
+            if let Some(mem_machine) = mem_table.get_var(name) {
+                self.write_mem_machine(mem_machine);
+            }
+        }
+        self.write_schema(mem_table.get_schema());
+        match mem_table.get_retention() {
+            None => self.write_u8(0),
+            Some(RetentionPolicy::Ticks(count)) => {
+                self.write_u8(1);
+                self.write_i64(count);
+            }
+            Some(RetentionPolicy::Values(count)) => {
+                self.write_u8(2);
+                self.write_u64(count as u64);
+            }
+            Some(RetentionPolicy::Watermark(logic_time)) => {
+                self.write_u8(3);
+                self.write_i64(logic_time);
+            }
+        }
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    version: u32,
+}
+
+impl<'a> SnapshotReader<'a> {
+    // hidden function
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], QueryError> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| QueryError::StorageError("snapshot is truncated".to_string()))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_array(&mut self) -> Result<[u8; 8], QueryError> {
+        let mut array = [0u8; 8];
+        array.copy_from_slice(self.read_bytes(8)?);
+        Ok(array)
+    }
+
+    // public function
+    pub fn new(bytes: &'a [u8], version: u32) -> Self {
+        SnapshotReader {
+            bytes,
+            position: 0,
+            version,
+        }
+    }
+
+    // To get version of snapshot which is read
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, QueryError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, QueryError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, QueryError> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    // count of items is checked against rest of bytes,
+    // so broken count can't allocate huge memory
+    pub fn read_count(&mut self) -> Result<usize, QueryError> {
+        let count = self.read_u64()?;
+        if count > (self.bytes.len() - self.position) as u64 {
+            return Err(QueryError::StorageError(format!("bad count in snapshot: {}", count)));
+        }
+        Ok(count as usize)
+    }
+
+    pub fn read_string(&mut self) -> Result<String, QueryError> {
+        let len = self.read_count()?;
+        let bytes = self.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| QueryError::StorageError("string of snapshot is not UTF-8".to_string()))
+    }
+
+    pub fn read_data_type(&mut self) -> Result<DataType, QueryError> {
+        match self.read_u8()? {
+            0 => Ok(DataType::Null),
+            1 => Ok(DataType::Int(self.read_i64()?)),
+            2 => Ok(DataType::Real(f64::from_bits(self.read_u64()?))),
+            3 => Ok(DataType::Text(self.read_string()?)),
+            4 => Ok(DataType::Symbol(self.read_string()?)),
+            tag => Err(QueryError::StorageError(format!("unknown value tag in snapshot: {}", tag))),
+        }
+    }
+
+    pub fn read_option_data_type(&mut self) -> Result<Option<DataType>, QueryError> {
+        match self.read_u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.read_data_type()?)),
+        }
+    }
+
+    pub fn read_schema(&mut self) -> Result<Schema, QueryError> {
+        let mut schema = Schema::new();
+        for _ in 0..self.read_count()? {
+            let name = self.read_string()?;
+            let value_type = match self.read_u8()? {
+                0 => None,
+                1 => Some(ValueType::Int),
+                2 => Some(ValueType::Real),
+                3 => Some(ValueType::Text),
+                4 => Some(ValueType::Symbol),
+                tag => return Err(QueryError::StorageError(format!("unknown type tag in snapshot: {}", tag))),
+            };
+            schema.declare(&name, value_type)?;
+        }
+        Ok(schema)
+    }
+
+    pub fn read_interval_set(&mut self) -> Result<IntervalSet, QueryError> {
+        let count = self.read_count()?;
+        let mut interval_set = IntervalSet::new();
+        for _ in 0..count {
+            let start = self.read_i64()?;
+            let end = self.read_i64()?;
+            interval_set.insert(start..=end);
+        }
+        Ok(interval_set)
+    }
+
+    pub fn read_mem_machine(&mut self) -> Result<MemoryMachine, QueryError> {
+        let logic_time = self.read_i64()?;
+        let last_value = self.read_option_data_type()?;
+        let mut values: Vec<(DataType, IntervalSet)> = Vec::new();
+        for _ in 0..self.read_count()? {
+            let data_type = self.read_data_type()?;
+            values.push((data_type, self.read_interval_set()?));
+        }
+        let mut timeline: Vec<(i64, DataType)> = Vec::new();
+        for _ in 0..self.read_count()? {
+            let logic_time = self.read_i64()?;
+            timeline.push((logic_time, self.read_data_type()?));
+        }
+        let deleted = self.read_interval_set()?;
+        Ok(MemoryMachine::restore(logic_time, last_value, values, timeline, deleted))
+    }
+
+    // Snapshot of version 1 has no schema, it's inferred from stored values.
+    // Snapshot before version 3 has no retention policy.
+    pub fn read_mem_table(&mut self) -> Result<MemoryTable, QueryError> {
+        let logic_time = self.read_i64()?;
+        let mut mem: HashMap<String, MemoryMachine> = HashMap::new();
+        for _ in 0..self.read_count()? {
+            let name = self.read_string()?;
+            mem.insert(name, self.read_mem_machine()?);
+        }
+        let schema = if self.get_version() >= 2 {
+            self.read_schema()?
+        } else {
+            Schema::infer(mem.iter().map(|(name, mem_machine)| {
+                let value_types = mem_machine
+                    .get_type_names()
+                    .into_iter()
+                    .filter_map(ValueType::from_name)
+                    .collect();
+                (name, value_types)
+            }))
+        };
+        let mut retention = None;
+        if self.get_version() >= 3 {
+            retention = match self.read_u8()? {
+                0 => None,
+                1 => Some(RetentionPolicy::Ticks(self.read_i64()?)),
+                2 => Some(RetentionPolicy::Values(self.read_u64()? as usize)),
+                3 => Some(RetentionPolicy::Watermark(self.read_i64()?)),
+                tag => {
+                    return Err(QueryError::StorageError(format!(
+                        "unknown retention tag in snapshot: {}",
+                        tag
+                    )))
+                }
+            };
+        }
+        Ok(MemoryTable::restore(logic_time, mem, schema, retention))
+    }
+}
+
+// Save and load of full channel.
+// wal_generation - generation of write-ahead log which continues snapshot,
+// records of older generations are already in snapshot.
+pub trait Snapshot: Sized {
+    fn encode_snapshot(&self, wal_generation: u64) -> Vec<u8>;
+    fn decode_snapshot(bytes: &[u8]) -> Result<(Self, u64), QueryError>;
+
+    fn save_snapshot(&self, path: &Path, wal_generation: u64) -> Result<(), QueryError> {
+        write_snapshot_file(path, &self.encode_snapshot(wal_generation))
+    }
+
+    fn load_snapshot(path: &Path) -> Result<(Self, u64), QueryError> {
+        let bytes = fs::read(path)
+            .map_err(|error| QueryError::StorageError(format!("{}: {}", path.display(), error)))?;
+        Self::decode_snapshot(&bytes)
+    }
+}
+
+// To encode tables of any access to channels, e.g. of locked concurrent channel
+pub fn encode_channels<C: ChannelAccess>(channels: &C, wal_generation: u64) -> Vec<u8> {
+    let tables = channels.get_tables();
+    let mut writer = SnapshotWriter::new();
+    writer.write_u64(wal_generation);
+    writer.write_u64(tables.len() as u64);
+    for (channel_name, mem_table) in tables {
+        writer.write_str(channel_name);
+        writer.write_mem_table(mem_table);
+    }
+
+    let mut bytes = Vec::with_capacity(writer.bytes.len() + 12);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&writer.bytes);
+    bytes.extend_from_slice(&crc32(&writer.bytes).to_le_bytes());
+    bytes
+}
+
+// snapshot is written to temporary file and renamed,
+// so crash during save keeps previous snapshot
+pub fn write_snapshot_file(path: &Path, bytes: &[u8]) -> Result<(), QueryError> {
+    let to_error = |error: std::io::Error| {
+        QueryError::StorageError(format!("{}: {}", path.display(), error))
+    };
+    let temporary_path = path.with_extension("tmp");
+    let mut file = File::create(&temporary_path).map_err(to_error)?;
+    file.write_all(bytes).map_err(to_error)?;
+    file.sync_all().map_err(to_error)?;
+    fs::rename(&temporary_path, path).map_err(to_error)
+}
+
+impl Snapshot for MemoryChannel {
+    fn encode_snapshot(&self, wal_generation: u64) -> Vec<u8> {
+        encode_channels(self, wal_generation)
+    }
+
+    fn decode_snapshot(bytes: &[u8]) -> Result<(Self, u64), QueryError> {
+        if bytes.len() < 12 || &bytes[..4] != MAGIC {
+            return Err(QueryError::StorageError("not a qdb snapshot".to_string()));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[4..8]);
+        let version = u32::from_le_bytes(version);
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(QueryError::StorageError(format!(
+                "unsupported snapshot version: {}",
+                version
+            )));
+        }
+
+        let body = &bytes[8..bytes.len() - 4];
+        let mut checksum = [0u8; 4];
+        checksum.copy_from_slice(&bytes[bytes.len() - 4..]);
+        if crc32(body) != u32::from_le_bytes(checksum) {
+            return Err(QueryError::StorageError("snapshot checksum mismatch".to_string()));
+        }
+
+        let mut reader = SnapshotReader::new(body, version);
+        let wal_generation = reader.read_u64()?;
+        let mut mem_channel = MemoryChannel::new();
+        for _ in 0..reader.read_count()? {
+            let channel_name = reader.read_string()?;
+            let mem_table = reader.read_mem_table()?;
+            mem_channel.insert(channel_name, mem_table);
+        }
+        if !reader.is_end() {
+            return Err(QueryError::StorageError("snapshot has trailing bytes".to_string()));
+        }
+        Ok((mem_channel, wal_generation))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_channel::MemoryChannel;
+    use crate::error::query_error::QueryError;
+    use crate::query::query_resolver::QueryResolver;
+    use crate::storage::snapshot::{crc32, Snapshot};
+    use std::fs;
+
+    #[test]
+    fn test_snapshot() {
+        debug_assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+
+        let mut origin = MemoryChannel::new();
+        for line in &[
+            "onCreate(my_node)(c:int = 2, d:real = 1.5)",
+            "onCreate(my_node)(c:int = 2, e:text = \"x\")",
+            "onCreate(my_node)(c:int = 3, f:symbol = y)",
+            "onDelete(my_node)(c == 3)",
+            "onCreate(other_node)(c:int = 1)",
+            "retain(other_node)(values:2)",
+        ] {
+            QueryResolver::resolve(&mut origin, line.to_string()).unwrap();
+        }
+
+        let path = std::env::temp_dir().join("qdb_snapshot.snapshot");
+        origin.save_snapshot(&path, 7).unwrap();
+        let (restored, wal_generation) = MemoryChannel::load_snapshot(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        debug_assert_eq!(7, wal_generation);
+        debug_assert_eq!(origin.len(), restored.len());
+        for (channel_name, origin_table) in origin.iter() {
+            let restored_table = restored.get(channel_name).unwrap();
+            debug_assert_eq!(origin_table.get_logic_time(), restored_table.get_logic_time());
+            debug_assert_eq!(origin_table.get_schema(), restored_table.get_schema());
+            debug_assert_eq!(origin_table.get_retention(), restored_table.get_retention());
+            for logic_time in 0..origin_table.get_logic_time() {
+                debug_assert_eq!(origin_table.read_at(logic_time), restored_table.read_at(logic_time));
+            }
+            debug_assert_eq!(
+                origin_table.read_between(0, origin_table.get_logic_time() - 1),
+                restored_table.read_between(0, restored_table.get_logic_time() - 1)
+            );
+        }
+        // encoding is stable, so snapshot of restored channel is the same
+        debug_assert_eq!(origin.encode_snapshot(7), restored.encode_snapshot(7));
+
+        let mut bytes = origin.encode_snapshot(7);
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 1;
+        debug_assert_eq!(
+            Some(QueryError::StorageError("snapshot checksum mismatch".to_string())),
+            MemoryChannel::decode_snapshot(&bytes).err()
+        );
+
+        let mut bytes = origin.encode_snapshot(7);
+        bytes[4] = 99;
+        debug_assert_eq!(
+            Some(QueryError::StorageError("unsupported snapshot version: 99".to_string())),
+            MemoryChannel::decode_snapshot(&bytes).err()
+        );
+    }
+}
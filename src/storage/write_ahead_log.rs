@@ -0,0 +1,383 @@
+use crate::memory::memory_channel::MemoryChannel;
+use crate::error::query_error::QueryError;
+use crate::query::query_resolver::QueryResolver;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+// Append-only log of mutating queries.
+// One record is one line: logic times of query channels before query
+// was applied and escaped query, separated by tab:
+//     my_node@3,other_node@0\tonUpdate(my_node)(c == 2)(c = 5)
+// Record is written (and synced by policy) before query is applied,
+// so replay of log rebuilds every channel with identical ranges.
+// First line of log is header with generation of log.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncPolicy {
+    // sync after every record
+    Always,
+    // sync after given count of records
+    EveryRecords(usize),
+    // leave sync to operating system
+    Never,
+}
+
+impl FromStr for SyncPolicy {
+    type Err = String;
+
+    // always | never | every:<count>
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "always" => Ok(SyncPolicy::Always),
+            "never" => Ok(SyncPolicy::Never),
+            _ => value
+                .strip_prefix("every:")
+                .and_then(|count| count.parse().ok())
+                .filter(|count| *count > 0)
+                .map(SyncPolicy::EveryRecords)
+                .ok_or_else(|| format!("invalid sync policy: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalRecord {
+    logic_times: Vec<(String, i64)>,
+    query: String,
+}
+
+impl WalRecord {
+    // hidden function
+    fn escape(query: &str) -> String {
+        let mut result = String::with_capacity(query.len());
+        for symbol in query.chars() {
+            match symbol {
+                '\\' => result.push_str("\\\\"),
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                _ => result.push(symbol),
+            }
+        }
+        result
+    }
+
+    fn unescape(query: &str) -> Result<String, QueryError> {
+        let mut result = String::with_capacity(query.len());
+        let mut symbols = query.chars();
+        while let Some(symbol) = symbols.next() {
+            if symbol != '\\' {
+                result.push(symbol);
+                continue;
+            }
+            match symbols.next() {
+                Some('\\') => result.push('\\'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                _ => return Err(QueryError::StorageError(format!("bad escape in log record: {}", query))),
+            }
+        }
+        Ok(result)
+    }
+
+    // public function
+    pub fn new(logic_times: Vec<(String, i64)>, query: String) -> Self {
+        WalRecord { logic_times, query }
+    }
+
+    pub fn get_logic_times(&self) -> &[(String, i64)] {
+        &self.logic_times
+    }
+
+    pub fn get_query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn encode(&self) -> String {
+        let logic_times: Vec<String> = self
+            .logic_times
+            .iter()
+            .map(|(channel_name, logic_time)| format!("{}@{}", channel_name, logic_time))
+            .collect();
+        format!("{}\t{}\n", logic_times.join(","), Self::escape(&self.query))
+    }
+
+    pub fn decode(line: &str) -> Result<Self, QueryError> {
+        let bad_record = || QueryError::StorageError(format!("bad log record: {}", line));
+        let (logic_times, query) = line.split_once('\t').ok_or_else(bad_record)?;
+
+        let mut result = Vec::new();
+        for logic_time in logic_times.split(',').filter(|part| !part.is_empty()) {
+            let (channel_name, logic_time) = logic_time.rsplit_once('@').ok_or_else(bad_record)?;
+            let logic_time = logic_time.parse().map_err(|_| bad_record())?;
+            result.push((channel_name.to_string(), logic_time));
+        }
+        Ok(WalRecord::new(result, Self::unescape(query)?))
+    }
+}
+
+// file starts with generation of log, log gets next generation
+// when its records are saved to snapshot
+const HEADER: &str = "qdb-wal ";
+
+pub struct WriteAheadLog {
+    file: File,
+    sync_policy: SyncPolicy,
+    unsynced_records: usize,
+    generation: u64,
+    records_count: usize,
+}
+
+impl WriteAheadLog {
+    // hidden function
+    fn storage_error(path: &Path, error: std::io::Error) -> QueryError {
+        QueryError::StorageError(format!("{}: {}", path.display(), error))
+    }
+
+    // Content of log without torn tail.
+    // Last line without line break is a record interrupted by crash.
+    fn read_content(path: &Path) -> Result<String, QueryError> {
+        let mut content = String::new();
+        match File::open(path) {
+            Ok(mut file) => {
+                file.read_to_string(&mut content)
+                    .map_err(|error| Self::storage_error(path, error))?;
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(Self::storage_error(path, error)),
+        }
+        let valid_len = content.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        content.truncate(valid_len);
+        Ok(content)
+    }
+
+    // log without header has generation 0
+    fn parse_content(content: &str) -> Result<(u64, Vec<WalRecord>), QueryError> {
+        let mut lines = content.lines().peekable();
+        let mut generation = 0;
+        if let Some(header) = lines.peek().and_then(|line| line.strip_prefix(HEADER)) {
+            generation = header.parse().map_err(|_| {
+                QueryError::StorageError(format!("bad log header: {}{}", HEADER, header))
+            })?;
+            lines.next();
+        }
+        let records = lines.map(WalRecord::decode).collect::<Result<Vec<_>, _>>()?;
+        Ok((generation, records))
+    }
+
+    fn write_header(&mut self) -> Result<(), QueryError> {
+        let header = format!("{}{}\n", HEADER, self.generation);
+        self.file
+            .write_all(header.as_bytes())
+            .map_err(|error| QueryError::StorageError(error.to_string()))?;
+        self.sync()
+    }
+
+    // public function
+    // To open log for appending, torn tail is cut off
+    pub fn open(path: &Path, sync_policy: SyncPolicy) -> Result<Self, QueryError> {
+        let content = Self::read_content(path)?;
+        let (generation, records) = Self::parse_content(&content)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|error| Self::storage_error(path, error))?;
+        file.set_len(content.len() as u64)
+            .map_err(|error| Self::storage_error(path, error))?;
+
+        let mut wal = WriteAheadLog {
+            file,
+            sync_policy,
+            unsynced_records: 0,
+            generation,
+            records_count: records.len(),
+        };
+        if content.is_empty() {
+            wal.write_header()?;
+        }
+        Ok(wal)
+    }
+
+    pub fn append(&mut self, record: &WalRecord) -> Result<(), QueryError> {
+        let to_error = |error: std::io::Error| QueryError::StorageError(error.to_string());
+        self.file.write_all(record.encode().as_bytes()).map_err(to_error)?;
+        self.unsynced_records += 1;
+        self.records_count += 1;
+
+        let is_sync_needed = match self.sync_policy {
+            SyncPolicy::Always => true,
+            SyncPolicy::EveryRecords(count) => self.unsynced_records >= count,
+            SyncPolicy::Never => false,
+        };
+        if is_sync_needed {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    pub fn sync(&mut self) -> Result<(), QueryError> {
+        self.file
+            .sync_data()
+            .map_err(|error| QueryError::StorageError(error.to_string()))?;
+        self.unsynced_records = 0;
+        Ok(())
+    }
+
+    // To drop all records and start log of given generation
+    pub fn reset(&mut self, generation: u64) -> Result<(), QueryError> {
+        self.file
+            .set_len(0)
+            .map_err(|error| QueryError::StorageError(error.to_string()))?;
+        self.generation = generation;
+        self.records_count = 0;
+        self.write_header()
+    }
+
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    // To get count of records written since start of generation
+    pub fn get_records_count(&self) -> usize {
+        self.records_count
+    }
+
+    // To get generation and records of log
+    pub fn read_records(path: &Path) -> Result<(u64, Vec<WalRecord>), QueryError> {
+        Self::parse_content(&Self::read_content(path)?)
+    }
+
+    // To apply records of log to memory channel, returns count of applied records.
+    // Log of generation older than given one is already in snapshot and skipped.
+    // Query error of record is skipped: the query failed the same way
+    // when it was logged, so state after it is the same.
+    pub fn replay(
+        path: &Path,
+        mem_channel: &mut MemoryChannel,
+        generation: u64,
+    ) -> Result<usize, QueryError> {
+        let (log_generation, records) = Self::read_records(path)?;
+        if log_generation < generation {
+            return Ok(0);
+        }
+        if log_generation > generation {
+            return Err(QueryError::StorageError(format!(
+                "log generation {} is newer than snapshot generation {}",
+                log_generation, generation
+            )));
+        }
+
+        for (number, record) in records.iter().enumerate() {
+            for (channel_name, logic_time) in record.get_logic_times() {
+                let current_logic_time = mem_channel
+                    .get(channel_name)
+                    .map(|mem_table| mem_table.get_logic_time())
+                    .unwrap_or(0);
+                if current_logic_time != *logic_time {
+                    return Err(QueryError::StorageError(format!(
+                        "log record {} expects {} at logic time {}, found {}",
+                        number + 1,
+                        channel_name,
+                        logic_time,
+                        current_logic_time
+                    )));
+                }
+            }
+            let _ = QueryResolver::resolve(mem_channel, record.get_query().to_string());
+        }
+        Ok(records.len())
+    }
+}
+
+impl Drop for WriteAheadLog {
+    fn drop(&mut self) {
+        if self.unsynced_records > 0 {
+            let _ = self.file.sync_data();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::memory_channel::MemoryChannel;
+    use crate::query::query_resolver::QueryResolver;
+    use crate::storage::write_ahead_log::{SyncPolicy, WalRecord, WriteAheadLog};
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_wal_record_encode() {
+        let record = WalRecord::new(
+            vec![("my_node".to_string(), 3), ("other_node".to_string(), 0)],
+            "onCreate(my_node, other_node)(c:text = \"a\tb\\\n\")".to_string(),
+        );
+        let line = record.encode();
+        debug_assert_eq!(
+            "my_node@3,other_node@0\tonCreate(my_node, other_node)(c:text = \"a\\tb\\\\\\n\")\n",
+            line
+        );
+        debug_assert_eq!(record, WalRecord::decode(line.trim_end_matches('\n')).unwrap());
+        debug_assert!(WalRecord::decode("my_node@x\tonRead(my_node)()").is_err());
+
+        debug_assert_eq!(Ok(SyncPolicy::EveryRecords(8)), "every:8".parse());
+        debug_assert!("every:0".parse::<SyncPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_write_ahead_log_replay() {
+        let path = std::env::temp_dir().join("qdb_write_ahead_log_replay.wal");
+        let _ = fs::remove_file(&path);
+
+        let mut origin = MemoryChannel::new();
+        {
+            let mut wal = WriteAheadLog::open(&path, SyncPolicy::Always).unwrap();
+            for line in &[
+                "onCreate(my_node)(c:int = 2, d:int = 1)",
+                "onCreate(my_node)(c:int = 3)",
+                "onUpdate(my_node)(c == 3)(d = 7)",
+                "onDelete(my_node)(c == 2)",
+                "onCreate(other_node)(e:text = \"x\")",
+            ] {
+                QueryResolver::resolve_with_log(&mut origin, &mut wal, line.to_string()).unwrap();
+            }
+            // reads are not logged
+            QueryResolver::resolve_with_log(&mut origin, &mut wal, "onRead(my_node)()".to_string())
+                .unwrap();
+        }
+        // torn tail of interrupted append
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"my_node@4\tonCreate(my_no").unwrap();
+
+        let mut restored = MemoryChannel::new();
+        debug_assert_eq!(5, WriteAheadLog::replay(&path, &mut restored, 0).unwrap());
+        for channel_name in &["my_node", "other_node"] {
+            let channel_name = channel_name.to_string();
+            let origin_table = origin.get(&channel_name).unwrap();
+            let restored_table = restored.get(&channel_name).unwrap();
+            debug_assert_eq!(origin_table.get_logic_time(), restored_table.get_logic_time());
+            debug_assert_eq!(
+                origin_table.read_between(0, origin_table.get_logic_time() - 1),
+                restored_table.read_between(0, restored_table.get_logic_time() - 1)
+            );
+        }
+
+        // log diverged from state
+        debug_assert!(WriteAheadLog::replay(&path, &mut restored, 0).is_err());
+
+        // reopening cuts torn tail off
+        let mut wal = WriteAheadLog::open(&path, SyncPolicy::Never).unwrap();
+        debug_assert!(fs::read_to_string(&path).unwrap().ends_with('\n'));
+        debug_assert_eq!(5, wal.get_records_count());
+
+        // log of older generation is already in snapshot
+        wal.reset(1).unwrap();
+        debug_assert_eq!("qdb-wal 1\n", fs::read_to_string(&path).unwrap());
+        debug_assert_eq!(0, WriteAheadLog::replay(&path, &mut MemoryChannel::new(), 2).unwrap());
+        debug_assert!(WriteAheadLog::replay(&path, &mut MemoryChannel::new(), 0).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}